@@ -0,0 +1,500 @@
+//! The interpreter as it was before functions were compiled into a [cho_lib::plan::Plan], kept so benchmarks can compare against it.
+//!
+//! It resolves routing, inputs and dependencies by scanning the connections of the function for every node it runs, and passes inputs by name. Connection values live in a vector next to the function instead of on a clone of it, which is the only shortcut it takes over the original.
+//!
+//! Not meant for anything but measurement. Use [Script::call_func]
+use std::sync::Arc;
+
+use log::Level;
+
+use cho_lib::{
+    nodes::{
+        is_reserved_pin, CallNode, LocalAccess, LocalNode, Node, NodeData, NodeError, FLOW_BREAK,
+        FLOW_CONTINUE, FLOW_LOOP,
+    },
+    scripts::{Function, FunctionNode, Script},
+    types::{GlobalName, NamespacedType, PinType, StringName, Var, VarRegisters},
+    Environment,
+};
+
+/// Executes a function of a script like [Script::call_func], without its plan
+pub fn call_func(
+    script: &Script,
+    func_name: StringName,
+    env: Arc<Environment>,
+    inputs: VarRegisters,
+) -> Result<VarRegisters, NodeError> {
+    call_func_at_depth(script, func_name, env, inputs, 0)
+}
+
+#[derive(Default)]
+struct FrameResults {
+    blackboard: VarRegisters,
+    next_nodes: Vec<usize>,
+    looping: bool,
+    signal: ExecSignal,
+}
+
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+enum ExecSignal {
+    #[default]
+    Completed,
+    Break,
+    Continue,
+}
+
+struct CallFrame<'a> {
+    script: &'a Script,
+    func_name: StringName,
+    func: &'a Function,
+    /// Value of every connection, indexed like [Function::routing]
+    values: Vec<Var>,
+    env: Arc<Environment>,
+    inputs: VarRegisters,
+    locals: VarRegisters,
+    produced: VarRegisters,
+    call_stack: Vec<usize>,
+    trace: Vec<usize>,
+    exits: Vec<StringName>,
+    depth: usize,
+}
+
+fn call_func_at_depth(
+    script: &Script,
+    func_name: StringName,
+    env: Arc<Environment>,
+    inputs: VarRegisters,
+    depth: usize,
+) -> Result<VarRegisters, NodeError> {
+    if depth > env.max_call_depth {
+        return Err(NodeError::StackOverflow {
+            name: GlobalName::from_path(format!(
+                "{}{}{}",
+                script.name.to_path(),
+                CallNode::DELIM,
+                func_name
+            )),
+            depth,
+        });
+    }
+    let Some(func) = script.funcs.get(&func_name) else {
+        return Err(NodeError::TypeNotFound {
+            name: GlobalName::from_path(func_name.to_string()),
+            msg: format!("Function not found on script: {:?}", script.name),
+        });
+    };
+    if env.logger.enabled(Level::Debug) {
+        env.logger.debug(format!(
+            "calling function {:?} with inputs: \n{:#?}",
+            func_name, inputs
+        ));
+    }
+    if func.nodes().get(func.entry).is_none() {
+        return Err(NodeError::Unhandled(format!("Failed to find entry node for function {:?}::{:?} at index {} of node array with {} elements", script.name, func_name, func.entry, func.nodes().len())));
+    }
+    let inputs = bind_inputs(func, &script.name, inputs)?;
+    let locals = init_locals(func);
+    let mut frame = CallFrame {
+        script,
+        func_name,
+        func,
+        values: vec![Var::Null; func.routing().len()],
+        env,
+        produced: inputs.clone(),
+        inputs,
+        locals,
+        call_stack: Vec::new(),
+        trace: Vec::new(),
+        exits: Vec::new(),
+        depth,
+    };
+
+    match frame.run_exec(vec![func.entry])? {
+        ExecSignal::Completed => {
+            let mut produced = frame.produced;
+            produced.0.extend(frame.locals.0);
+            Ok(collect_outputs(func, produced, &frame.exits))
+        }
+        signal => Err(NodeError::Unhandled(format!(
+            "Received {:?} outside of any loop in function {:?}::{:?}",
+            signal, script.name, frame.func_name
+        ))),
+    }
+}
+
+impl CallFrame<'_> {
+    fn run_exec(&mut self, start: Vec<usize>) -> Result<ExecSignal, NodeError> {
+        let mut exec_stack = start.into_iter().rev().collect::<Vec<_>>();
+
+        while let Some(index) = exec_stack.pop() {
+            let node = self.get_node(index)?;
+            let mut results = self.run_node(&node, VarRegisters::new())?;
+            if results.signal != ExecSignal::Completed {
+                return Ok(results.signal);
+            }
+
+            while results.looping {
+                let body_start = self.trace.len();
+                let signal = self.run_exec(results.next_nodes)?;
+                self.reset_loop_body(body_start);
+
+                let mut state = results.blackboard;
+                if signal == ExecSignal::Break {
+                    state.0.insert(FLOW_BREAK.into(), Var::Execution(true));
+                }
+                results = self.run_node(&node, state)?;
+            }
+
+            for index in results.next_nodes.into_iter().rev() {
+                exec_stack.push(index);
+            }
+        }
+        Ok(ExecSignal::Completed)
+    }
+
+    fn run_node(
+        &mut self,
+        node: &FunctionNode,
+        extra_inputs: VarRegisters,
+    ) -> Result<FrameResults, NodeError> {
+        self.refresh_volatile(node);
+        self.call_stack.push(node.index);
+        self.backfill(node)?;
+        self.call_stack.pop();
+
+        let mut inputs = self.get_input_register(node)?;
+        inputs.0.extend(extra_inputs.0);
+
+        let results = self.execute_frame(inputs, node)?;
+        for entry in results.blackboard.0.iter() {
+            if !is_reserved_pin(entry.0.as_str()) {
+                self.produced.0.insert(entry.0.clone(), entry.1.clone());
+            }
+        }
+        Ok(results)
+    }
+
+    fn reset_loop_body(&mut self, body_start: usize) {
+        let body = &self.trace[body_start..];
+        let stale = self
+            .func
+            .nodes()
+            .iter()
+            .filter(|node| node.node.is_pure() || body.contains(&node.index))
+            .map(|node| node.index)
+            .collect::<Vec<_>>();
+        for (route, value) in self.func.routing().iter().zip(self.values.iter_mut()) {
+            if stale.contains(&route.from) {
+                *value = Var::Null;
+            }
+        }
+    }
+
+    fn get_node(&self, index: usize) -> Result<FunctionNode, NodeError> {
+        self.func.nodes().get(index).cloned().ok_or_else(|| {
+            NodeError::Unhandled(format!(
+                "Failed to find node for function {:?}::{:?} at index {} of node array with {} elements",
+                self.script.name,
+                self.func_name,
+                index,
+                self.func.nodes().len()
+            ))
+        })
+    }
+
+    fn backfill(&mut self, node: &FunctionNode) -> Result<(), NodeError> {
+        while let Some(source) = self.get_backfill_nodes(node).first().cloned() {
+            let source_ref = self.get_node(source)?;
+            if self.call_stack.contains(&source) {
+                return Err(NodeError::Unhandled(format!(
+                    "Data cycle detected in function {:?}::{:?} while evaluating {:?}",
+                    self.script.name, self.func_name, source_ref
+                )));
+            }
+            if self.env.logger.enabled(Level::Debug) {
+                self.env
+                    .logger
+                    .debug(format!("Backfilling node: {:?}", source_ref));
+            }
+
+            self.call_stack.push(source);
+            self.refresh_volatile(&source_ref);
+            self.backfill(&source_ref)?;
+            let inputs = self.get_input_register(&source_ref)?;
+            let results = self.execute_frame(inputs, &source_ref)?;
+            self.call_stack.pop();
+
+            for entry in results.blackboard.0 {
+                self.produced.0.insert(entry.0, entry.1);
+            }
+
+            if let Some((route, _)) =
+                self.func
+                    .routing()
+                    .iter()
+                    .zip(self.values.iter())
+                    .find(|(route, value)| {
+                        route.from == source && route.to == node.index && **value == Var::Null
+                    })
+            {
+                return Err(NodeError::NullException {
+                    name: source_ref.node.get_name(),
+                    arg: route.from_param.clone(),
+                    msg: "Node did not produce a value for a connected output".into(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn get_input_register(&self, node: &FunctionNode) -> Result<VarRegisters, NodeError> {
+        let mut registers = VarRegisters::new();
+        for (route, value) in self.func.routing().iter().zip(self.values.iter()) {
+            if route.to == node.index && *value != Var::Null && !value.is_execution() {
+                registers.0.insert(route.to_param.clone(), value.clone());
+            }
+        }
+        let exec_inputs = node.node.get_exec_inputs();
+        for req in node.node.get_inputs() {
+            if registers.0.contains_key(&req) || exec_inputs.contains(&req) {
+                continue;
+            }
+            if let Some(value) = node.constants.0.get(&req) {
+                registers.0.insert(req, value.clone());
+                continue;
+            }
+            if let Some(entry) = self.inputs.0.get(&req) {
+                registers.0.insert(req, entry.clone());
+                continue;
+            }
+            if node.node.has_default(&req) {
+                continue;
+            }
+            return Err(NodeError::NullException {
+                name: node.node.get_name(),
+                arg: req.clone(),
+                msg: "Failed to find valid input between connections and function inputs".into(),
+            });
+        }
+        Ok(registers)
+    }
+
+    fn refresh_volatile(&mut self, node: &FunctionNode) {
+        let nodes = &self.func.nodes();
+        for (route, value) in self.func.routing().iter().zip(self.values.iter_mut()) {
+            if route.to == node.index
+                && nodes
+                    .get(route.from)
+                    .is_some_and(|source| source.node.is_volatile())
+            {
+                *value = Var::Null;
+            }
+        }
+    }
+
+    fn access_local(
+        &mut self,
+        local: &LocalNode,
+        inputs: VarRegisters,
+    ) -> Result<VarRegisters, NodeError> {
+        let mut outputs = VarRegisters::new();
+        match local.access {
+            LocalAccess::Get => {
+                let value = self
+                    .locals
+                    .0
+                    .get(&local.name)
+                    .cloned()
+                    .unwrap_or_else(|| local.ty.default_value());
+                outputs.0.insert("value".into(), value);
+            }
+            LocalAccess::Set => {
+                let value = inputs.0.get(&"value".into()).cloned().unwrap_or_default();
+                if value != Var::Null && !local.ty.matches(&value) {
+                    return Err(NodeError::MismatchedData {
+                        name: local.get_name(),
+                        arg: "value".into(),
+                        expected: local.ty.default_value(),
+                        received: value,
+                        msg: format!("Expected {} for local {:?}", local.ty, local.name),
+                    });
+                }
+                self.locals.0.insert(local.name.clone(), value.clone());
+                outputs.0.insert("then".into(), Var::Execution(true));
+                outputs.0.insert("value".into(), value);
+            }
+        }
+        Ok(outputs)
+    }
+
+    fn get_backfill_nodes(&self, node: &FunctionNode) -> Vec<usize> {
+        let func = self.func;
+        func.routing()
+            .iter()
+            .zip(self.values.iter())
+            .filter(|(route, value)| route.to == node.index && **value == Var::Null)
+            .filter(|(route, _)| !func.is_exec_connection(route))
+            .filter(|(route, _)| {
+                func.nodes()
+                    .get(route.from)
+                    .is_some_and(|source| source.node.is_pure())
+            })
+            .map(|(route, _)| route.from)
+            .collect()
+    }
+
+    fn call(&self, call: &CallNode, inputs: VarRegisters) -> Result<VarRegisters, NodeError> {
+        let Some(name) = &call.script else {
+            return call_func_at_depth(
+                self.script,
+                call.func.clone(),
+                self.env.clone(),
+                inputs,
+                self.depth + 1,
+            );
+        };
+        let Some(script) = self.env.scripts.get(name) else {
+            return Err(NodeError::TypeNotFound {
+                name: name.clone(),
+                msg: format!("No script registered for call to {:?}", call.to_path()),
+            });
+        };
+        call_func_at_depth(
+            &script,
+            call.func.clone(),
+            self.env.clone(),
+            inputs,
+            self.depth + 1,
+        )
+    }
+
+    fn execute_frame(
+        &mut self,
+        inputs: VarRegisters,
+        node: &FunctionNode,
+    ) -> Result<FrameResults, NodeError> {
+        if self.env.logger.enabled(Level::Debug) {
+            self.env
+                .logger
+                .debug(format!("Executing {:?} with inputs: \n{:#?}", node, inputs));
+        }
+        let next_frame = match node.node.as_ref() {
+            Node::Call(call) => self.call(call, inputs)?,
+            Node::Local(local) => self.access_local(local, inputs)?,
+            Node::Script(script) => call_func_at_depth(
+                &script.script,
+                script.func.clone(),
+                self.env.clone(),
+                inputs,
+                self.depth + 1,
+            )?,
+            other => other.execute(self.env.clone(), inputs)?,
+        };
+        self.trace.push(node.index);
+
+        let mut results = FrameResults::default();
+        let mut connected = Vec::new();
+        for (route, value) in self.func.routing().iter().zip(self.values.iter_mut()) {
+            if route.from != node.index {
+                continue;
+            }
+            connected.push(route.from_param.clone());
+            let Some(var) = next_frame.0.get(&route.from_param) else {
+                continue;
+            };
+            if *var == Var::Execution(true) {
+                results.next_nodes.push(route.to);
+            }
+            *value = var.clone();
+        }
+
+        for (key, var) in next_frame.0 {
+            if var == Var::Execution(true) {
+                match key.as_str() {
+                    FLOW_LOOP => results.looping = true,
+                    FLOW_BREAK => results.signal = ExecSignal::Break,
+                    FLOW_CONTINUE => results.signal = ExecSignal::Continue,
+                    _ if !connected.contains(&key) => self.exits.push(key.clone()),
+                    _ => (),
+                }
+            }
+            if !var.is_execution() {
+                results.blackboard.0.insert(key, var);
+            }
+        }
+
+        Ok(results)
+    }
+}
+
+// the call helpers of [Function] are private to the library, so the bench carries its own copies
+
+fn bind_inputs(
+    func: &Function,
+    name: &GlobalName,
+    mut inputs: VarRegisters,
+) -> Result<VarRegisters, NodeError> {
+    for param in func.inputs.iter().filter(|p| p.ty != PinType::Exec) {
+        let value = match inputs.0.get(&param.name) {
+            Some(value) if *value != Var::Null => value.clone(),
+            _ => {
+                if let Some(default) = &param.default {
+                    inputs.0.insert(param.name.clone(), default.clone());
+                }
+                continue;
+            }
+        };
+        if !param.ty.matches(&value) {
+            return Err(NodeError::MismatchedData {
+                name: name.clone(),
+                arg: param.name.clone(),
+                expected: param.ty.default_value(),
+                received: value,
+                msg: format!("Expected {} for function input", param.ty),
+            });
+        }
+    }
+    Ok(inputs)
+}
+
+fn init_locals(func: &Function) -> VarRegisters {
+    VarRegisters(
+        func.locals
+            .iter()
+            .map(|local| {
+                let value = local
+                    .default
+                    .clone()
+                    .unwrap_or_else(|| local.ty.default_value());
+                (local.name.clone(), value)
+            })
+            .collect(),
+    )
+}
+
+fn collect_outputs(func: &Function, produced: VarRegisters, exits: &[StringName]) -> VarRegisters {
+    if func.outputs.is_empty() {
+        return produced;
+    }
+    let completed = func
+        .outputs
+        .iter()
+        .filter(|param| param.ty == PinType::Exec)
+        .count()
+        == 1;
+    let mut outputs = VarRegisters::new();
+    for param in func.outputs.iter() {
+        let value = match param.ty {
+            PinType::Exec => Var::Execution(completed || exits.contains(&param.name)),
+            _ => produced
+                .0
+                .get(&param.name)
+                .filter(|value| **value != Var::Null)
+                .cloned()
+                .or_else(|| param.default.clone())
+                .unwrap_or_default(),
+        };
+        outputs.0.insert(param.name.clone(), value);
+    }
+    outputs
+}
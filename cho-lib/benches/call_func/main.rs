@@ -0,0 +1,127 @@
+//! Times repeated function calls through the interpreter, next to the interpreter from before execution plans. Run with `cargo bench -p cho-lib`
+use std::{
+    collections::HashMap,
+    hint::black_box,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+mod legacy;
+
+use cho_lib::{
+    filetype::{ScriptProto, Versioned},
+    scripts::Script,
+    types::{Var, VarRegisters},
+    Environment,
+};
+
+const SCRIPT_TEXT: &str = r#"
+(
+    global_name: "bench.script",
+    funcs: [
+        (
+            "chain", FunctionProto(
+                entry: "scaled",
+                nodes: {
+                    "x": "std.math.add",
+                    "y": "std.math.subtract",
+                    "sum": "std.math.add",
+                    "scaled": (node: "std.math.multiply", constants: {"b": Num(2.0)}),
+                },
+                connections: [
+                    "x.c -> y.b",
+                    "x.c -> sum.b",
+                    "y.c -> sum.a",
+                    "sum.c -> scaled.a",
+                ],
+            )
+        ),
+        (
+            "loop", FunctionProto(
+                entry: "loop",
+                nodes: {
+                    "loop": (node: "std.control.for_range", constants: {"start": Int(0), "end": Int(100)}),
+                    "read": "local.get.total",
+                    "sum": "std.math.add",
+                    "store": "local.set.total",
+                },
+                connections: [
+                    "loop.body -> store.exec",
+                    "loop.index -> sum.a",
+                    "read.value -> sum.b",
+                    "sum.c -> store.value",
+                ],
+                outputs: [(name: "total", ty: Num)],
+                locals: [(name: "total", ty: Num)],
+            )
+        ),
+    ],
+)
+"#;
+
+/// Time per call of `f`
+fn time(iterations: u32, mut f: impl FnMut()) -> Duration {
+    // warm up caches before measuring
+    for _ in 0..iterations / 10 {
+        f();
+    }
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    start.elapsed() / iterations
+}
+
+fn bench(
+    script: &Script,
+    env: &Arc<Environment>,
+    func: &'static str,
+    inputs: &VarRegisters,
+    iterations: u32,
+) {
+    let planned = time(iterations, || {
+        let output = script
+            .call_func(func.into(), env.clone(), inputs.clone())
+            .unwrap();
+        black_box(output);
+    });
+    let unplanned = time(iterations, || {
+        let output = legacy::call_func(script, func.into(), env.clone(), inputs.clone()).unwrap();
+        black_box(output);
+    });
+    println!(
+        "{func:<12} {iterations:>6} calls {:>10.2?}/call planned {:>10.2?}/call legacy {:>6.1}x",
+        planned,
+        unplanned,
+        unplanned.as_secs_f64() / planned.as_secs_f64()
+    );
+}
+
+fn main() {
+    let env = Environment::new();
+    let script = ScriptProto::from_ron(SCRIPT_TEXT)
+        .unwrap()
+        .to_script(&env)
+        .unwrap();
+    let env = Arc::new(env);
+    let inputs = VarRegisters(HashMap::from([
+        ("a".into(), Var::Num(3.0)),
+        ("b".into(), Var::Num(4.0)),
+    ]));
+
+    // make sure both interpreters are doing the work being timed
+    for func in ["chain", "loop"] {
+        let planned = script
+            .call_func(func.into(), env.clone(), inputs.clone())
+            .unwrap();
+        let legacy = legacy::call_func(&script, func.into(), env.clone(), inputs.clone()).unwrap();
+        assert_eq!(planned, legacy, "{func} differs between the interpreters");
+    }
+    let total = script
+        .call_func("loop".into(), env.clone(), inputs.clone())
+        .unwrap();
+    assert_eq!(total.0.get(&"total".into()), Some(&Var::Num(4950.0)));
+
+    bench(&script, &env, "chain", &inputs, 20000);
+    bench(&script, &env, "loop", &inputs, 1000);
+}
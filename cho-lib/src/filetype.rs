@@ -1,24 +1,182 @@
-use std::{cmp::Ordering, collections::HashMap};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BinaryHeap, HashMap},
+    sync::Arc,
+};
 
-use serde::{Deserialize, Serialize};
+use ron::ser::PrettyConfig;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use crate::{
-    nodes::Node,
-    scripts::{Connection, Function, Script},
-    types::{GlobalName, NamespacedType, TypeRegistry},
+    layout::Layout,
+    nodes::{CallNode, LocalNode, Node, NodeData, NodeError, NodeMeta},
+    scripts::{Connection, Function, Param, Script, TypeMismatch},
+    structs::StructDef,
+    types::{GlobalName, NamespacedType, PinType, RegistryError, StringName, Var, VarRegisters},
+    Environment,
 };
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ScriptProto {
+    /// Files written before versions were recorded read as version 0
+    #[serde(default)]
+    pub format_version: u32,
     pub global_name: String,
+    /// Global names of scripts this one calls into. They are loaded before this script so their functions can be linked
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub imports: Vec<String>,
     pub funcs: Vec<(String, FunctionProto)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub structs: Vec<StructProto>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StructProto {
+    pub name: String,
+    pub fields: Vec<(String, PinType)>,
+}
+
+/// A file format which records its version, so documents written by older versions can be upgraded when they are read
+pub trait Versioned: DeserializeOwned {
+    /// Version written by this build
+    const FORMAT_VERSION: u32;
+
+    fn format_version(&self) -> u32;
+
+    fn set_format_version(&mut self, version: u32);
+
+    /// Upgrades the document from `version` to the version after it. The document is already parsed, so a version may only add fields with defaults or rename them with a serde alias
+    fn upgrade_from(&mut self, version: u32) -> Result<(), FormatError>;
+
+    /// Runs every upgrade between the version of the document and [Versioned::FORMAT_VERSION], returning the version it was at
+    fn migrate(&mut self) -> Result<u32, FormatError> {
+        let found = self.format_version();
+        if found > Self::FORMAT_VERSION {
+            return Err(FormatError::TooNew {
+                found,
+                supported: Self::FORMAT_VERSION,
+            });
+        }
+        for version in found..Self::FORMAT_VERSION {
+            self.upgrade_from(version)?;
+        }
+        self.set_format_version(Self::FORMAT_VERSION);
+        Ok(found)
+    }
+
+    /// Parses a document and upgrades it to the latest version
+    fn from_ron(text: &str) -> Result<Self, FormatError> {
+        let mut doc =
+            ron::de::from_str::<Self>(text).map_err(|err| FormatError::Parse(err.to_string()))?;
+        doc.migrate()?;
+        Ok(doc)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormatError {
+    Parse(String),
+    /// The document was written by a newer build than this one
+    TooNew {
+        found: u32,
+        supported: u32,
+    },
+    /// A version older than the latest has no upgrade to the version after it
+    NoUpgrade {
+        from: u32,
+    },
+}
+
+/// A function as read from a file. References between nodes are always indices into `nodes`, whichever format the file used
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(try_from = "FunctionFile", into = "FunctionFile")]
 pub struct FunctionProto {
     pub entry: usize,
     pub nodes: Vec<String>,
+    /// Stable ids of the nodes, in the same order. When empty the function is written with positional indices
+    pub ids: Vec<String>,
     pub connections: Vec<ConnectionProto>,
+    /// Literal values for unconnected inputs, by node index. They are written inline with their node, so those for nodes out of range are dropped
+    pub constants: BTreeMap<usize, BTreeMap<String, Var>>,
+    pub inputs: Vec<ParamProto>,
+    pub outputs: Vec<ParamProto>,
+    /// Variables of each call, read and written with `local.get.name` and `local.set.name` nodes
+    pub locals: Vec<ParamProto>,
+    pub meta: NodeMeta,
+    /// Editor presentation, which is carried through to the [Function] untouched
+    pub layout: Layout,
+}
+
+/// How a [FunctionProto] is written. Nodes are either a list referred to by index, or a map of ids referred to by name, e.g. `"sum.c -> diff.b"`
+#[derive(Deserialize, Serialize)]
+#[serde(rename = "FunctionProto")]
+struct FunctionFile {
+    entry: NodeRef,
+    nodes: NodeList,
+    connections: Vec<ConnectionFile>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    inputs: Vec<ParamProto>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    outputs: Vec<ParamProto>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    locals: Vec<ParamProto>,
+    #[serde(default, skip_serializing_if = "NodeMeta::is_empty")]
+    meta: NodeMeta,
+    #[serde(default, skip_serializing_if = "LayoutFile::is_empty")]
+    layout: LayoutFile,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum NodeRef {
+    Index(usize),
+    Id(String),
+}
+
+/// Layout referring to nodes the same way as the rest of the function
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum LayoutFile {
+    Indexed(Layout),
+    Named(Layout<String>),
+}
+
+// deserialized by hand, since untagged enums buffer their content and RON loses the variant names of any [Var] in it
+#[derive(Serialize)]
+#[serde(untagged)]
+enum NodeList {
+    Indexed(Vec<NodeEntry>),
+    Named(NamedNodes),
+}
+
+/// Node entries by id, in file order since that order decides the index of each node
+struct NamedNodes(Vec<(String, NodeEntry)>);
+
+/// A node type, along with literal values for its inputs if it has any, e.g. `(node: "std.math.add", constants: {"b": Num(5.0)})`
+#[derive(Serialize)]
+#[serde(untagged)]
+enum NodeEntry {
+    Type(String),
+    WithConstants {
+        node: String,
+        constants: BTreeMap<String, Var>,
+    },
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum ConnectionFile {
+    Named(String),
+    Indexed(ConnectionProto),
+}
+
+/// A declared input or output of a function, which becomes a pin when the function is used as a node
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ParamProto {
+    pub name: String,
+    pub ty: PinType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Var>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -29,26 +187,134 @@ pub struct ConnectionProto {
     pub from_param: String,
 }
 
+/// Resolves the node names of a function while it is built, including calls to other functions
+pub struct Linker<'a> {
+    env: &'a Environment,
+    /// Functions of the script being built, which `::func` calls refer to
+    local: &'a [(String, FunctionProto)],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The function may still run, but likely not as intended
+    Warning,
+    /// The function cannot be built or run
+    Error,
+}
+
+/// A problem found while validating a [ScriptProto]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Name of the function the problem was found in
+    pub func: String,
+    /// Index of the node the problem concerns, if it concerns a single node
+    pub node: Option<usize>,
+    pub kind: DiagnosticKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiagnosticKind {
+    UnknownNodeType {
+        name: String,
+    },
+    /// A call to a function which no registered script, or the calling script, has
+    UnknownFunction {
+        name: String,
+    },
+    EntryOutOfRange {
+        entry: usize,
+        len: usize,
+    },
+    ConnectionOutOfRange {
+        connection: usize,
+        index: usize,
+        len: usize,
+    },
+    UnknownPin {
+        connection: usize,
+        pin: String,
+        is_input: bool,
+    },
+    /// Nodes which feed each other's data, in order around the cycle
+    DataCycle {
+        nodes: Vec<usize>,
+    },
+    /// A data input with no connection, which will only work if the caller provides a value of the same name
+    UnconnectedInput {
+        pin: String,
+    },
+    TypeMismatch(TypeMismatch),
+    /// A constant for a pin which is not a data input of its node
+    UnknownConstant {
+        pin: String,
+    },
+    /// An access to a local variable the function does not declare
+    UnknownLocal {
+        name: String,
+    },
+    /// A constant which a pin of its type cannot hold
+    ConstantMismatch {
+        pin: String,
+        expected: PinType,
+        value: Var,
+    },
+}
+
 impl ScriptProto {
     /// Generates an in-memory script from a given prototype, allowing for execution and registration of the parsed information
-    pub fn to_script(self, registry: &TypeRegistry<Node>) -> Script {
+    ///
+    /// Any declared structs need to be registered through [ScriptProto::register_structs] first, so that their nodes can be found.
+    ///
+    /// The script is validated first. If any errors are found, every diagnostic is returned instead of a script
+    pub fn to_script(self, env: &Environment) -> Result<Script, Vec<Diagnostic>> {
+        let diagnostics = self.validate(env);
+        if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+            return Err(diagnostics);
+        }
+        let linker = Linker::new(env).with_local(&self.funcs);
         let mut script = Script {
-            name: GlobalName::from_path(self.global_name),
+            name: GlobalName::from_path(self.global_name.clone()),
+            imports: self
+                .imports
+                .iter()
+                .cloned()
+                .map(GlobalName::from_path)
+                .collect(),
             funcs: HashMap::new(),
+            structs: self.structs.iter().map(StructProto::to_struct).collect(),
         };
-        for (name, proto) in self.funcs {
-            script
-                .funcs
-                .insert(name.into(), proto.to_function(registry));
+        for (name, proto) in self.funcs.iter() {
+            // validation has already resolved every node, so this cannot fail
+            let Ok(func) = proto.clone().to_function(&linker) else {
+                continue;
+            };
+            script.funcs.insert(name.clone().into(), func);
         }
-        script
+        Ok(script)
+    }
+
+    /// Checks every function of this script, reporting all problems found rather than stopping at the first
+    pub fn validate(&self, env: &Environment) -> Vec<Diagnostic> {
+        let linker = Linker::new(env).with_local(&self.funcs);
+        self.funcs
+            .iter()
+            .flat_map(|(name, func)| func.validate(name, &linker))
+            .collect()
     }
 
     /// Generates a prototype based on the in-memory script. Useful for making an editor
     pub fn from_script(script: &Script) -> Self {
         let mut proto = Self {
+            format_version: Self::FORMAT_VERSION,
             global_name: script.name.clone().to_path(),
+            imports: script.imports.iter().map(GlobalName::to_path).collect(),
             funcs: Vec::new(),
+            structs: script
+                .structs
+                .iter()
+                .map(StructProto::from_struct)
+                .collect(),
         };
         for (name, func) in script.funcs.iter() {
             proto.funcs.push((
@@ -56,12 +322,16 @@ impl ScriptProto {
                 FunctionProto {
                     entry: func.entry,
                     nodes: func
-                        .nodes
+                        .nodes()
                         .iter()
-                        .map(|f| f.node.get_name().to_path())
+                        .map(|f| match f.node.as_ref() {
+                            Node::Call(call) => call.to_path(),
+                            node => node.get_name().to_path(),
+                        })
                         .collect(),
+                    ids: func.ids.clone(),
                     connections: func
-                        .routing
+                        .routing()
                         .iter()
                         .map(|c| ConnectionProto {
                             from: c.from,
@@ -70,56 +340,867 @@ impl ScriptProto {
                             from_param: c.from_param.to_string(),
                         })
                         .collect(),
+                    constants: func
+                        .nodes()
+                        .iter()
+                        .filter(|f| !f.constants.0.is_empty())
+                        .map(|f| {
+                            let constants = f
+                                .constants
+                                .0
+                                .iter()
+                                .map(|(pin, value)| (pin.to_string(), value.clone()))
+                                .collect();
+                            (f.index, constants)
+                        })
+                        .collect(),
+                    inputs: func.inputs.iter().map(ParamProto::from_param).collect(),
+                    outputs: func.outputs.iter().map(ParamProto::from_param).collect(),
+                    locals: func.locals.iter().map(ParamProto::from_param).collect(),
+                    meta: func.meta.clone(),
+                    layout: func.layout.clone(),
                 },
             ));
         }
         proto
     }
 
-    /// Stabilizes data entries for better support from VCS
+    /// Registers the declared struct types and their generated nodes on the environment
+    pub fn register_structs(&self, env: &mut Environment) -> Result<(), RegistryError> {
+        for def in self.structs.iter() {
+            env.register_struct(def.to_struct())?;
+        }
+        Ok(())
+    }
+
+    /// Puts every list in a deterministic order for better support from VCS. Node references are rewritten to match, so the graph itself is unchanged
     pub fn stabilize(mut self) -> Self {
         self.funcs.sort_by(|a, b| a.0.cmp(&b.0));
+        self.imports.sort();
+        self.imports.dedup();
+        self.structs.sort_by(|a, b| a.name.cmp(&b.name));
         for (_, func) in self.funcs.iter_mut() {
-            func.nodes.sort_by(|a, b| a.cmp(b));
-
-            func.connections.sort_by(|a, b| match a.from.cmp(&b.from) {
-                Ordering::Less => Ordering::Less,
-                Ordering::Greater => Ordering::Greater,
-                Ordering::Equal => match a.to.cmp(&b.to) {
-                    Ordering::Less => Ordering::Less,
-                    Ordering::Greater => Ordering::Greater,
-                    Ordering::Equal => a.from_param.cmp(&b.from_param),
-                },
-            });
+            func.stabilize();
         }
         self
     }
+
+    /// Formatting every script file is written with, so that equal scripts are equal text
+    pub fn canonical_config() -> PrettyConfig {
+        PrettyConfig::new()
+            .indentor("    ".into())
+            .new_line("\n".into())
+            .separate_tuple_members(false)
+            .enumerate_arrays(false)
+            .struct_names(false)
+    }
+
+    /// Stabilizes this script and writes it with [ScriptProto::canonical_config]
+    pub fn to_canonical_string(self) -> Result<String, ron::Error> {
+        let mut text = ron::ser::to_string_pretty(&self.stabilize(), Self::canonical_config())?;
+        text.push('\n');
+        Ok(text)
+    }
+}
+
+impl StructProto {
+    pub fn to_struct(&self) -> StructDef {
+        StructDef::new(
+            GlobalName::from_path(self.name.clone()),
+            self.fields
+                .iter()
+                .map(|(key, pin)| (key.clone(), pin.clone())),
+        )
+    }
+
+    pub fn from_struct(def: &StructDef) -> Self {
+        Self {
+            name: def.name.to_path(),
+            fields: def
+                .fields
+                .iter()
+                .map(|(key, pin)| (key.to_string(), pin.clone()))
+                .collect(),
+        }
+    }
 }
 
 impl FunctionProto {
-    pub fn to_function(self, registry: &TypeRegistry<Node>) -> Function {
-        Function::new(
-            registry,
-            self.nodes
-                .into_iter()
-                .map(|f| GlobalName::from_path(f))
-                .collect::<Vec<GlobalName>>(),
+    /// Builds the in-memory function. Use [FunctionProto::validate] first to find out what is wrong when this fails
+    pub fn to_function(self, linker: &Linker) -> Result<Function, NodeError> {
+        let mut nodes = Vec::with_capacity(self.nodes.len());
+        for (index, name) in self.nodes.iter().enumerate() {
+            let node = self
+                .resolve(name, linker)
+                .map_err(|_| NodeError::TypeNotFound {
+                    name: GlobalName::from_path(name.clone()),
+                    msg: format!("Unknown node type for node #{index}"),
+                })?;
+            nodes.push(node);
+        }
+        let mut func = Function::from_nodes(
+            nodes,
             self.entry,
             self.connections
                 .into_iter()
                 .map(|c| Connection::new(c.from, c.to, c.from_param, c.to_param))
                 .collect(),
+        );
+        func.ids = self.ids;
+        for (index, constants) in self.constants {
+            if let Some(node) = func.nodes_mut().get_mut(index) {
+                node.constants = VarRegisters(
+                    constants
+                        .into_iter()
+                        .map(|(pin, value)| (pin.into(), value))
+                        .collect(),
+                );
+            }
+        }
+        func.inputs = self.inputs.into_iter().map(ParamProto::to_param).collect();
+        func.outputs = self.outputs.into_iter().map(ParamProto::to_param).collect();
+        func.locals = self.locals.into_iter().map(ParamProto::to_param).collect();
+        func.meta = self.meta;
+        func.layout = self.layout;
+        Ok(func)
+    }
+
+    /// Resolves a node name, including accesses to the local variables of this function
+    fn resolve(&self, name: &str, linker: &Linker) -> Result<Arc<Node>, DiagnosticKind> {
+        let Some((access, local)) = LocalNode::parse_path(name) else {
+            return linker.resolve(name);
+        };
+        let Some(param) = self
+            .locals
+            .iter()
+            .find(|param| local.as_str() == param.name)
+        else {
+            return Err(DiagnosticKind::UnknownLocal { name: name.into() });
+        };
+        Ok(Arc::new(Node::Local(LocalNode {
+            name: local,
+            ty: param.ty.clone(),
+            access,
+        })))
+    }
+
+    /// Checks the structure, pins and types of this function, reporting all problems found
+    pub fn validate(&self, func_name: &str, linker: &Linker) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let mut report = |severity: Severity, node: Option<usize>, kind: DiagnosticKind| {
+            diagnostics.push(Diagnostic {
+                severity,
+                func: func_name.to_string(),
+                node,
+                kind,
+            })
+        };
+
+        let mut nodes = Vec::with_capacity(self.nodes.len());
+        for (index, name) in self.nodes.iter().enumerate() {
+            match self.resolve(name, linker) {
+                Ok(node) => nodes.push(Some(node)),
+                Err(kind) => {
+                    report(Severity::Error, Some(index), kind);
+                    nodes.push(None);
+                }
+            }
+        }
+        if self.entry >= self.nodes.len() {
+            report(
+                Severity::Error,
+                None,
+                DiagnosticKind::EntryOutOfRange {
+                    entry: self.entry,
+                    len: self.nodes.len(),
+                },
+            );
+        }
+
+        for (index, conn) in self.connections.iter().enumerate() {
+            for (end, pin, is_input) in [
+                (conn.from, &conn.from_param, false),
+                (conn.to, &conn.to_param, true),
+            ] {
+                if end >= self.nodes.len() {
+                    report(
+                        Severity::Error,
+                        None,
+                        DiagnosticKind::ConnectionOutOfRange {
+                            connection: index,
+                            index: end,
+                            len: self.nodes.len(),
+                        },
+                    );
+                    continue;
+                }
+                // unknown types are already reported, and functions without a signature accept any pin
+                let Some(node) = &nodes[end] else {
+                    continue;
+                };
+                if !node.declares_pins() {
+                    continue;
+                }
+                let pins = match is_input {
+                    true => node.get_inputs(),
+                    false => node.get_outputs(),
+                };
+                if !StringName::lookup(pin).is_some_and(|pin| pins.contains(&pin)) {
+                    report(
+                        Severity::Error,
+                        Some(end),
+                        DiagnosticKind::UnknownPin {
+                            connection: index,
+                            pin: pin.clone(),
+                            is_input,
+                        },
+                    );
+                }
+            }
+        }
+
+        for (index, node) in nodes.iter().enumerate() {
+            let Some(node) = node else {
+                continue;
+            };
+            let exec_inputs = node.get_exec_inputs();
+            let constants = self.constants.get(&index);
+            if let (Some(constants), true) = (constants, node.declares_pins()) {
+                let types = node.get_input_types();
+                for (pin, value) in constants {
+                    let ty = StringName::lookup(pin)
+                        .filter(|pin| !exec_inputs.contains(pin))
+                        .and_then(|pin| types.0.get(&pin));
+                    match ty {
+                        Some(ty) => {
+                            if !ty.matches(value) {
+                                report(
+                                    Severity::Error,
+                                    Some(index),
+                                    DiagnosticKind::ConstantMismatch {
+                                        pin: pin.clone(),
+                                        expected: ty.clone(),
+                                        value: value.clone(),
+                                    },
+                                );
+                            }
+                        }
+                        _ => report(
+                            Severity::Error,
+                            Some(index),
+                            DiagnosticKind::UnknownConstant { pin: pin.clone() },
+                        ),
+                    }
+                }
+            }
+            for pin in node.get_inputs() {
+                if exec_inputs.contains(&pin)
+                    || node.has_default(&pin)
+                    || constants.is_some_and(|c| c.contains_key(&pin.to_string()))
+                {
+                    continue;
+                }
+                let connected = self
+                    .connections
+                    .iter()
+                    .any(|c| c.to == index && c.to_param == pin.as_str());
+                if !connected {
+                    report(
+                        Severity::Warning,
+                        Some(index),
+                        DiagnosticKind::UnconnectedInput {
+                            pin: pin.to_string(),
+                        },
+                    );
+                }
+            }
+        }
+
+        // the remaining checks need a function which can actually be built
+        if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+            return diagnostics;
+        }
+        let Ok(func) = self.clone().to_function(linker) else {
+            return diagnostics;
+        };
+        for nodes in func.find_data_cycles() {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                func: func_name.to_string(),
+                node: nodes.first().cloned(),
+                kind: DiagnosticKind::DataCycle { nodes },
+            });
+        }
+        for mismatch in func.check_types() {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                func: func_name.to_string(),
+                node: Some(mismatch.to),
+                kind: DiagnosticKind::TypeMismatch(mismatch),
+            });
+        }
+        diagnostics
+    }
+}
+
+impl FunctionProto {
+    /// Sorts nodes by id, or by type when they have no ids, remapping every index, then sorts connections with [Self::sort_connections]
+    fn stabilize(&mut self) {
+        let mut order = (0..self.nodes.len()).collect::<Vec<_>>();
+        // the sort is stable, so nodes of the same type keep their relative order
+        match self.ids.len() == self.nodes.len() {
+            true => order.sort_by(|a, b| self.ids[*a].cmp(&self.ids[*b])),
+            false => order.sort_by(|a, b| self.nodes[*a].cmp(&self.nodes[*b])),
+        }
+        let mut new_index = vec![0; self.nodes.len()];
+        for (new, old) in order.iter().enumerate() {
+            new_index[*old] = new;
+        }
+        // out of range references are left alone for validation to report
+        let remap = |index: usize| new_index.get(index).cloned().unwrap_or(index);
+
+        self.nodes = order.iter().map(|old| self.nodes[*old].clone()).collect();
+        if self.ids.len() == self.nodes.len() {
+            self.ids = order.iter().map(|old| self.ids[*old].clone()).collect();
+        }
+        self.entry = remap(self.entry);
+        for conn in self.connections.iter_mut() {
+            conn.from = remap(conn.from);
+            conn.to = remap(conn.to);
+        }
+        self.constants = std::mem::take(&mut self.constants)
+            .into_iter()
+            .map(|(node, constants)| (remap(node), constants))
+            .collect();
+        self.sort_connections();
+
+        let mut layout = std::mem::take(&mut self.layout)
+            .map_nodes(|node| Ok::<_, ()>(remap(node)))
+            .unwrap_or_default();
+        layout.reroutes.sort_by(|a, b| {
+            (a.from, &a.from_param, a.to, &a.to_param).cmp(&(
+                b.from,
+                &b.from_param,
+                b.to,
+                &b.to_param,
+            ))
+        });
+        for group in layout.groups.iter_mut() {
+            group.nodes.sort();
+            group.nodes.dedup();
+        }
+        self.layout = layout;
+    }
+
+    /// Sorts connections by their ends, except that connections leaving the same node or driving the same input keep
+    /// their order, since it decides which branch fires first and which value an input ends up with
+    fn sort_connections(&mut self) {
+        let key = |c: &ConnectionProto| (c.from, c.from_param.clone(), c.to, c.to_param.clone());
+        // every connection waits on the one before it from the same node and into the same pin
+        let mut waiting = vec![0; self.connections.len()];
+        let mut next = vec![Vec::new(); self.connections.len()];
+        let mut last_from = HashMap::new();
+        let mut last_to = HashMap::new();
+        for (i, conn) in self.connections.iter().enumerate() {
+            let before = [
+                last_from.insert(conn.from, i),
+                last_to.insert((conn.to, conn.to_param.clone()), i),
+            ];
+            for prev in before.into_iter().flatten() {
+                waiting[i] += 1;
+                next[prev].push(i);
+            }
+        }
+        let mut ready = (0..self.connections.len())
+            .filter(|i| waiting[*i] == 0)
+            .map(|i| Reverse((key(&self.connections[i]), i)))
+            .collect::<BinaryHeap<_>>();
+        let mut order = Vec::with_capacity(self.connections.len());
+        while let Some(Reverse((_, i))) = ready.pop() {
+            order.push(i);
+            for after in std::mem::take(&mut next[i]) {
+                waiting[after] -= 1;
+                if waiting[after] == 0 {
+                    ready.push(Reverse((key(&self.connections[after]), after)));
+                }
+            }
+        }
+        self.connections = order.iter().map(|i| self.connections[*i].clone()).collect();
+    }
+}
+
+impl ConnectionProto {
+    pub const ARROW: &str = "->";
+
+    /// Parses `from.pin -> to.pin`, looking up node ids with `index_of`
+    fn parse(text: &str, index_of: impl Fn(&str) -> Option<usize>) -> Result<Self, String> {
+        let malformed = || format!("Connection {text:?} is not of the form \"from.pin -> to.pin\"");
+        let (from, to) = text.split_once(Self::ARROW).ok_or_else(malformed)?;
+        let (from, from_param) = from.trim().split_once('.').ok_or_else(malformed)?;
+        let (to, to_param) = to.trim().split_once('.').ok_or_else(malformed)?;
+        let find = |id: &str| {
+            index_of(id).ok_or_else(|| format!("Connection {text:?} refers to unknown node {id:?}"))
+        };
+        Ok(Self {
+            from: find(from)?,
+            to: find(to)?,
+            to_param: to_param.into(),
+            from_param: from_param.into(),
+        })
+    }
+
+    fn to_named(&self, ids: &[String]) -> String {
+        format!(
+            "{}.{} {} {}.{}",
+            ids[self.from],
+            self.from_param,
+            Self::ARROW,
+            ids[self.to],
+            self.to_param
         )
     }
 }
+
+impl TryFrom<FunctionFile> for FunctionProto {
+    type Error = String;
+
+    fn try_from(file: FunctionFile) -> Result<Self, Self::Error> {
+        let (entries, ids): (Vec<_>, Vec<_>) = match file.nodes {
+            NodeList::Indexed(entries) => (entries, Vec::new()),
+            NodeList::Named(NamedNodes(named)) => {
+                named.into_iter().map(|(id, entry)| (entry, id)).unzip()
+            }
+        };
+        let mut nodes = Vec::with_capacity(entries.len());
+        let mut constants = BTreeMap::new();
+        for (index, entry) in entries.into_iter().enumerate() {
+            match entry {
+                NodeEntry::Type(node) => nodes.push(node),
+                NodeEntry::WithConstants {
+                    node,
+                    constants: values,
+                } => {
+                    nodes.push(node);
+                    if !values.is_empty() {
+                        constants.insert(index, values);
+                    }
+                }
+            }
+        }
+        for (index, id) in ids.iter().enumerate() {
+            if id.is_empty() || id.contains('.') || id.contains(char::is_whitespace) {
+                return Err(format!(
+                    "Node id {id:?} must be non-empty without dots or whitespace"
+                ));
+            }
+            if ids[..index].contains(id) {
+                return Err(format!("Node id {id:?} is used more than once"));
+            }
+        }
+        let index_of = |id: &str| ids.iter().position(|known| known == id);
+
+        let entry = match file.entry {
+            NodeRef::Index(index) => index,
+            NodeRef::Id(id) => {
+                index_of(&id).ok_or_else(|| format!("Entry refers to unknown node {id:?}"))?
+            }
+        };
+        let connections = file
+            .connections
+            .into_iter()
+            .map(|conn| match conn {
+                ConnectionFile::Indexed(conn) => Ok(conn),
+                ConnectionFile::Named(text) => ConnectionProto::parse(&text, index_of),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let layout = match file.layout {
+            LayoutFile::Indexed(layout) => layout,
+            LayoutFile::Named(layout) => layout.map_nodes(|id| {
+                index_of(&id).ok_or_else(|| format!("Layout refers to unknown node {id:?}"))
+            })?,
+        };
+        Ok(Self {
+            entry,
+            nodes,
+            ids,
+            connections,
+            constants,
+            inputs: file.inputs,
+            outputs: file.outputs,
+            locals: file.locals,
+            meta: file.meta,
+            layout,
+        })
+    }
+}
+
+impl From<FunctionProto> for FunctionFile {
+    fn from(mut proto: FunctionProto) -> Self {
+        let entries = std::mem::take(&mut proto.nodes)
+            .into_iter()
+            .enumerate()
+            .map(|(index, node)| match proto.constants.remove(&index) {
+                Some(constants) if !constants.is_empty() => {
+                    NodeEntry::WithConstants { node, constants }
+                }
+                _ => NodeEntry::Type(node),
+            })
+            .collect::<Vec<_>>();
+        let in_range = |index: usize| index < proto.ids.len();
+        let named = !proto.ids.is_empty()
+            && proto.ids.len() == entries.len()
+            && in_range(proto.entry)
+            && proto
+                .connections
+                .iter()
+                .all(|c| in_range(c.from) && in_range(c.to))
+            && proto.layout.nodes.keys().all(|node| in_range(*node))
+            && proto
+                .layout
+                .reroutes
+                .iter()
+                .all(|r| in_range(r.from) && in_range(r.to))
+            && proto
+                .layout
+                .groups
+                .iter()
+                .all(|g| g.nodes.iter().all(|node| in_range(*node)));
+        // references which cannot be named are kept as indices, so nothing is lost
+        if !named {
+            return Self {
+                entry: NodeRef::Index(proto.entry),
+                nodes: NodeList::Indexed(entries),
+                connections: proto
+                    .connections
+                    .into_iter()
+                    .map(ConnectionFile::Indexed)
+                    .collect(),
+                inputs: proto.inputs,
+                outputs: proto.outputs,
+                locals: proto.locals,
+                meta: proto.meta,
+                layout: LayoutFile::Indexed(proto.layout),
+            };
+        }
+        let ids = &proto.ids;
+        let layout = proto
+            .layout
+            .map_nodes(|node| Ok::<_, ()>(ids[node].clone()))
+            .unwrap_or_default();
+        Self {
+            entry: NodeRef::Id(proto.ids[proto.entry].clone()),
+            connections: proto
+                .connections
+                .iter()
+                .map(|c| ConnectionFile::Named(c.to_named(&proto.ids)))
+                .collect(),
+            nodes: NodeList::Named(NamedNodes(proto.ids.into_iter().zip(entries).collect())),
+            inputs: proto.inputs,
+            outputs: proto.outputs,
+            locals: proto.locals,
+            meta: proto.meta,
+            layout: LayoutFile::Named(layout),
+        }
+    }
+}
+
+impl Default for LayoutFile {
+    fn default() -> Self {
+        Self::Indexed(Layout::default())
+    }
+}
+
+impl LayoutFile {
+    fn is_empty(&self) -> bool {
+        match self {
+            LayoutFile::Indexed(layout) => layout.is_empty(),
+            LayoutFile::Named(layout) => layout.is_empty(),
+        }
+    }
+}
+
+impl Serialize for NamedNodes {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (id, node) in self.0.iter() {
+            map.serialize_entry(id, node)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for NodeList {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct NodeListVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for NodeListVisitor {
+            type Value = NodeList;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a list of nodes or a map of node ids to nodes")
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                self,
+                mut access: A,
+            ) -> Result<Self::Value, A::Error> {
+                let mut nodes = Vec::new();
+                while let Some(entry) = access.next_element::<NodeEntry>()? {
+                    nodes.push(entry);
+                }
+                Ok(NodeList::Indexed(nodes))
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(
+                self,
+                mut access: A,
+            ) -> Result<Self::Value, A::Error> {
+                let mut nodes = Vec::new();
+                while let Some(entry) = access.next_entry::<String, NodeEntry>()? {
+                    nodes.push(entry);
+                }
+                Ok(NodeList::Named(NamedNodes(nodes)))
+            }
+        }
+
+        // a map keeps the order of the file, which decides the index of every node
+        deserializer.deserialize_any(NodeListVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for NodeEntry {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct NodeEntryVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for NodeEntryVisitor {
+            type Value = NodeEntry;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a node type, or a node type with constants")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, node: &str) -> Result<Self::Value, E> {
+                Ok(NodeEntry::Type(node.into()))
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(
+                self,
+                mut access: A,
+            ) -> Result<Self::Value, A::Error> {
+                #[derive(Deserialize)]
+                #[serde(field_identifier, rename_all = "lowercase")]
+                enum Field {
+                    Node,
+                    Constants,
+                }
+
+                let (mut node, mut constants) = (None, BTreeMap::new());
+                while let Some(key) = access.next_key::<Field>()? {
+                    match key {
+                        Field::Node => node = Some(access.next_value::<String>()?),
+                        Field::Constants => constants = access.next_value()?,
+                    }
+                }
+                let node = node.ok_or_else(|| serde::de::Error::missing_field("node"))?;
+                Ok(NodeEntry::WithConstants { node, constants })
+            }
+        }
+
+        deserializer.deserialize_any(NodeEntryVisitor)
+    }
+}
+
+impl<'a> Linker<'a> {
+    pub fn new(env: &'a Environment) -> Self {
+        Self { env, local: &[] }
+    }
+
+    pub fn with_local(mut self, local: &'a [(String, FunctionProto)]) -> Self {
+        self.local = local;
+        self
+    }
+
+    /// Looks up a registered node, or links a call to the function a `script::func` name refers to
+    pub fn resolve(&self, name: &str) -> Result<Arc<Node>, DiagnosticKind> {
+        let Some((script, func)) = CallNode::parse_path(name) else {
+            return GlobalName::lookup_path(name)
+                .and_then(|path| self.env.nodes.get(&path))
+                .ok_or_else(|| DiagnosticKind::UnknownNodeType { name: name.into() });
+        };
+        let unknown = || DiagnosticKind::UnknownFunction { name: name.into() };
+        let (inputs, outputs) = match &script {
+            None => {
+                let (_, proto) = self
+                    .local
+                    .iter()
+                    .find(|(local, _)| *local == func.as_str())
+                    .ok_or_else(unknown)?;
+                (
+                    proto
+                        .inputs
+                        .iter()
+                        .cloned()
+                        .map(ParamProto::to_param)
+                        .collect(),
+                    proto
+                        .outputs
+                        .iter()
+                        .cloned()
+                        .map(ParamProto::to_param)
+                        .collect(),
+                )
+            }
+            Some(script) => {
+                let script = self.env.scripts.get(script).ok_or_else(unknown)?;
+                let target = script.funcs.get(&func).ok_or_else(unknown)?;
+                (target.inputs.clone(), target.outputs.clone())
+            }
+        };
+        Ok(Arc::new(Node::Call(CallNode {
+            script,
+            func,
+            inputs,
+            outputs,
+        })))
+    }
+}
+
+impl ParamProto {
+    pub fn to_param(self) -> Param {
+        Param {
+            name: self.name.into(),
+            ty: self.ty,
+            default: self.default,
+        }
+    }
+
+    pub fn from_param(param: &Param) -> Self {
+        Self {
+            name: param.name.to_string(),
+            ty: param.ty.clone(),
+            default: param.default.clone(),
+        }
+    }
+}
+
+impl Versioned for ScriptProto {
+    const FORMAT_VERSION: u32 = 3;
+
+    fn format_version(&self) -> u32 {
+        self.format_version
+    }
+
+    fn set_format_version(&mut self, version: u32) {
+        self.format_version = version;
+    }
+
+    /// Versions 0 to 2 are read-compatible with the latest: every field they lack has a default, so there is nothing to rewrite
+    fn upgrade_from(&mut self, version: u32) -> Result<(), FormatError> {
+        match version {
+            // version 1 only started recording the version
+            0 => Ok(()),
+            // version 2 allows constants in node entries, which older builds cannot read
+            1 => Ok(()),
+            // version 3 adds local variables, which older builds cannot resolve
+            2 => Ok(()),
+            from => Err(FormatError::NoUpgrade { from }),
+        }
+    }
+}
+
+impl std::fmt::Display for FormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FormatError::Parse(msg) => write!(f, "{}", msg),
+            FormatError::TooNew { found, supported } => write!(
+                f,
+                "format version {} is newer than the latest supported version {}",
+                found, supported
+            ),
+            FormatError::NoUpgrade { from } => {
+                write!(f, "no upgrade from format version {}", from)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.severity {
+            Severity::Warning => write!(f, "warning: ")?,
+            Severity::Error => write!(f, "error: ")?,
+        }
+        write!(f, "in function {:?}", self.func)?;
+        if let Some(node) = self.node {
+            write!(f, ", node #{node}")?;
+        }
+        write!(f, ": ")?;
+        match &self.kind {
+            DiagnosticKind::UnknownNodeType { name } => write!(f, "unknown node type {name:?}"),
+            DiagnosticKind::UnknownFunction { name } => {
+                write!(f, "call to unknown function {name:?}")
+            }
+            DiagnosticKind::EntryOutOfRange { entry, len } => {
+                write!(f, "entry #{entry} is out of range for {len} nodes")
+            }
+            DiagnosticKind::ConnectionOutOfRange {
+                connection,
+                index,
+                len,
+            } => write!(
+                f,
+                "connection #{connection} refers to node #{index}, but there are only {len} nodes"
+            ),
+            DiagnosticKind::UnknownPin {
+                connection,
+                pin,
+                is_input,
+            } => write!(
+                f,
+                "connection #{connection} refers to unknown {} pin {pin:?}",
+                if *is_input { "input" } else { "output" }
+            ),
+            DiagnosticKind::DataCycle { nodes } => write!(f, "data cycle between nodes {nodes:?}"),
+            DiagnosticKind::UnconnectedInput { pin } => write!(
+                f,
+                "input {pin:?} is not connected and must be provided by the caller"
+            ),
+            DiagnosticKind::TypeMismatch(mismatch) => write!(f, "{mismatch}"),
+            DiagnosticKind::UnknownLocal { name } => {
+                write!(f, "access to undeclared local {name:?}")
+            }
+            DiagnosticKind::UnknownConstant { pin } => {
+                write!(f, "constant for unknown input pin {pin:?}")
+            }
+            DiagnosticKind::ConstantMismatch {
+                pin,
+                expected,
+                value,
+            } => write!(
+                f,
+                "constant {value:?} does not fit input {pin:?} of type {expected:?}"
+            ),
+        }
+    }
+}
 #[cfg(test)]
 mod test {
 
-    use crate::{types::GlobalName, Environment};
+    use std::{collections::HashMap, sync::Arc};
 
-    use super::ScriptProto;
+    use crate::{
+        nodes::{Node, NodeData, NodeError, ScriptNode},
+        types::{GlobalName, Globals, PinType, Var, VarRegisters},
+        Environment,
+    };
 
-    const SCRIPT_TEXT: &'static str = r#"
+    use super::{
+        ConnectionProto, Diagnostic, DiagnosticKind, FormatError, FunctionProto, Linker,
+        ParamProto, ScriptProto, Severity, Versioned,
+    };
+
+    const SCRIPT_TEXT: &str = r#"
 (
     global_name: "test.script",
     funcs: [
@@ -135,20 +1216,20 @@ mod test {
                     ConnectionProto(
                         from: 0,
                         to: 1,
-                        to_param: "c",
-                        from_param: "b",                
+                        to_param: "a",
+                        from_param: "c",
                     ),
                     ConnectionProto(
                         from: 0,
                         to: 2,
-                        to_param: "c",
-                        from_param: "b",                
+                        to_param: "a",
+                        from_param: "c",
                     ),
                     ConnectionProto(
                         from: 1,
                         to: 2,
-                        to_param: "c",
-                        from_param: "a",                
+                        to_param: "b",
+                        from_param: "c",
                     ),
                 ],     
             )
@@ -158,18 +1239,844 @@ mod test {
 
     #[test]
     fn test_deser() {
-        let read_res = ron::de::from_str::<ScriptProto>(&SCRIPT_TEXT);
+        let read_res = ron::de::from_str::<ScriptProto>(SCRIPT_TEXT);
         let proto = read_res.unwrap();
         eprintln!("Prototype: {:#?}", proto);
         let env = Environment::new();
 
-        let script = proto.to_script(&env.nodes);
+        let script = proto.to_script(&env).unwrap();
         assert_eq!(script.name, GlobalName::from_path("test.script"));
         assert_eq!(script.funcs.len(), 1);
         assert!(script.funcs.contains_key(&"func".into()));
         let func = script.funcs.get(&"func".into()).unwrap();
         assert_eq!(func.entry, 2);
-        assert_eq!(func.nodes.len(), 3);
-        assert_eq!(func.routing.len(), 3);
+        assert_eq!(func.nodes().len(), 3);
+        assert_eq!(func.routing().len(), 3);
+    }
+
+    const STRUCT_SCRIPT_TEXT: &str = r#"
+(
+    global_name: "game.script",
+    structs: [
+        (
+            name: "game.Player",
+            fields: [
+                ("hp", Num),
+                ("name", String),
+            ],
+        ),
+    ],
+    funcs: [
+        (
+            "func", FunctionProto(
+                entry: 1,
+                nodes: [
+                    "game.Player.make",
+                    "game.Player.break",
+                ],
+                connections: [
+                    ConnectionProto(
+                        from: 0,
+                        to: 1,
+                        to_param: "struct",
+                        from_param: "struct",
+                    ),
+                ],
+            )
+        )
+    ],
+)"#;
+
+    #[test]
+    fn test_deser_structs() {
+        let proto = ron::de::from_str::<ScriptProto>(STRUCT_SCRIPT_TEXT).unwrap();
+        let mut env = Environment::new();
+        proto.register_structs(&mut env).unwrap();
+        assert!(env.structs.contains(&GlobalName::from_path("game.Player")));
+
+        let script = proto.to_script(&env).unwrap();
+        assert_eq!(script.structs.len(), 1);
+        let func = script.funcs.get(&"func".into()).unwrap();
+        assert_eq!(func.nodes().len(), 2);
+
+        let output = script
+            .call_func(
+                "func".into(),
+                Arc::new(env),
+                VarRegisters(HashMap::from([
+                    ("hp".into(), Var::Num(5.0)),
+                    ("name".into(), Var::String("hero".into())),
+                ])),
+            )
+            .unwrap();
+        assert_eq!(output.0.get(&"hp".into()).cloned(), Some(Var::Num(5.0)));
+
+        // the declared structs survive a round trip through the prototype
+        let round_trip = ScriptProto::from_script(&script);
+        assert_eq!(round_trip.structs.len(), 1);
+        assert_eq!(round_trip.structs[0].name, "game.Player");
+    }
+
+    fn param(name: &str, ty: PinType) -> ParamProto {
+        ParamProto {
+            name: name.into(),
+            ty,
+            default: None,
+        }
+    }
+
+    fn connection(from: usize, from_param: &str, to: usize, to_param: &str) -> ConnectionProto {
+        ConnectionProto {
+            from,
+            to,
+            to_param: to_param.into(),
+            from_param: from_param.into(),
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let env = Environment::new();
+        let proto = FunctionProto {
+            entry: 3,
+            nodes: vec![
+                "std.math.add".into(),
+                "std.math.missing".into(),
+                "std.math.add".into(),
+            ],
+            connections: vec![connection(0, "c", 5, "a"), connection(0, "sum", 2, "a")],
+            ..Default::default()
+        };
+        let diagnostics = proto.validate("main", &Linker::new(&env));
+        let kinds = diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Error)
+            .map(|d| d.kind.clone())
+            .collect::<Vec<_>>();
+        assert!(kinds.contains(&DiagnosticKind::UnknownNodeType {
+            name: "std.math.missing".into()
+        }));
+        assert!(kinds.contains(&DiagnosticKind::EntryOutOfRange { entry: 3, len: 3 }));
+        assert!(kinds.contains(&DiagnosticKind::ConnectionOutOfRange {
+            connection: 0,
+            index: 5,
+            len: 3
+        }));
+        assert!(kinds.contains(&DiagnosticKind::UnknownPin {
+            connection: 1,
+            pin: "sum".into(),
+            is_input: false
+        }));
+        // the unknown node is not silently dropped, so the function cannot be built
+        assert!(proto.to_function(&Linker::new(&env)).is_err());
+    }
+
+    #[test]
+    fn test_validate_cycles() {
+        let env = Environment::new();
+        let proto = FunctionProto {
+            entry: 0,
+            nodes: vec!["std.math.add".into(), "std.math.add".into()],
+            connections: vec![connection(0, "c", 1, "a"), connection(1, "c", 0, "a")],
+            ..Default::default()
+        };
+        let diagnostics = proto.validate("main", &Linker::new(&env));
+        assert!(diagnostics.iter().any(|d| d.severity == Severity::Error
+            && matches!(&d.kind, DiagnosticKind::DataCycle { nodes } if nodes.len() == 2)));
+        // inputs nothing is connected to are only warnings
+        let unconnected = diagnostics
+            .iter()
+            .filter(|d| matches!(d.kind, DiagnosticKind::UnconnectedInput { .. }))
+            .collect::<Vec<_>>();
+        assert_eq!(unconnected.len(), 2);
+        assert!(unconnected.iter().all(|d| d.severity == Severity::Warning));
+    }
+
+    #[test]
+    fn test_function_meta() {
+        let text = r#"
+(
+    global_name: "game.util",
+    funcs: [
+        (
+            "main", FunctionProto(
+                entry: 0,
+                nodes: ["std.math.add"],
+                connections: [],
+                meta: (
+                    description: "Adds things",
+                    tags: ["sum"],
+                    pins: {
+                        "a": (description: "Left side", default: Some(Num(1.0))),
+                    },
+                ),
+            )
+        )
+    ],
+)"#;
+        let proto = ron::de::from_str::<ScriptProto>(text).unwrap();
+        let env = Environment::new();
+        let script = proto.to_script(&env).unwrap();
+        let node = ScriptNode {
+            name: GlobalName::from_path("game.util"),
+            func: "main".into(),
+            script: Arc::new(script),
+        };
+        let meta = node.get_meta();
+        assert_eq!(meta.description, "Adds things");
+        assert_eq!(meta.tags, vec!["sum".to_string()]);
+        // scripts without a category are listed under their namespace
+        assert_eq!(meta.category, "game");
+        assert_eq!(meta.pins["a"].default, Some(Var::Num(1.0)));
+
+        let add = env
+            .nodes
+            .get(&GlobalName::from_path("std.math.add"))
+            .unwrap();
+        assert_eq!(add.get_meta().category, "math");
+    }
+
+    const HELPER_SCRIPT_TEXT: &str = r#"
+(
+    global_name: "test.helper",
+    funcs: [
+        (
+            "main", FunctionProto(
+                entry: 0,
+                nodes: ["std.math.add"],
+                connections: [],
+                inputs: [
+                    (name: "a", ty: Num),
+                    (name: "b", ty: Num, default: Some(Num(10.0))),
+                ],
+                outputs: [(name: "c", ty: Num)],
+            )
+        )
+    ],
+)"#;
+
+    #[test]
+    fn test_function_signature() {
+        let mut env = Environment::new();
+        let helper = ron::de::from_str::<ScriptProto>(HELPER_SCRIPT_TEXT)
+            .unwrap()
+            .to_script(&env)
+            .unwrap();
+        let node = ScriptNode {
+            name: GlobalName::from_path("test.helper"),
+            func: "main".into(),
+            script: Arc::new(helper),
+        };
+        assert_eq!(node.get_outputs(), vec!["c".into()]);
+        assert_eq!(
+            node.get_input_types().0.get(&"a".into()),
+            Some(&PinType::Num)
+        );
+        env.nodes.register(Node::Script(node));
+
+        // the script's pins are wired like those of any other node
+        let proto = FunctionProto {
+            entry: 1,
+            nodes: vec!["test.helper".into(), "std.math.multiply".into()],
+            connections: vec![connection(0, "c", 1, "a"), connection(0, "c", 1, "b")],
+            ..Default::default()
+        };
+        let script = ScriptProto {
+            format_version: ScriptProto::FORMAT_VERSION,
+            global_name: "test.caller".into(),
+            funcs: vec![("main".into(), proto.clone())],
+            imports: Vec::new(),
+            structs: Vec::new(),
+        }
+        .to_script(&env)
+        .unwrap();
+        let output = script
+            .call_func(
+                "main".into(),
+                Arc::new(env.clone()),
+                VarRegisters(HashMap::from([("a".into(), Var::Num(2.0))])),
+            )
+            .unwrap();
+        // (2 + 10) squared, with the helper's default filling in for b
+        assert_eq!(output.0.get(&"c".into()), Some(&Var::Num(144.0)));
+
+        let mut bad = proto;
+        bad.connections = vec![connection(0, "sum", 1, "a")];
+        let diagnostics = bad.validate("main", &Linker::new(&env));
+        assert!(diagnostics.iter().any(|d| d.kind
+            == DiagnosticKind::UnknownPin {
+                connection: 0,
+                pin: "sum".into(),
+                is_input: false
+            }));
+        // defaulted inputs do not need a connection
+        assert!(!diagnostics
+            .iter()
+            .any(|d| d.node == Some(0)
+                && d.kind == DiagnosticKind::UnconnectedInput { pin: "b".into() }));
+    }
+
+    #[test]
+    fn test_function_calls() {
+        let mut env = Environment::new();
+        let helper = ron::de::from_str::<ScriptProto>(HELPER_SCRIPT_TEXT)
+            .unwrap()
+            .to_script(&env)
+            .unwrap();
+        env.scripts.register(helper);
+
+        let square = FunctionProto {
+            entry: 0,
+            nodes: vec!["std.math.multiply".into()],
+            inputs: vec![param("a", PinType::Num), param("b", PinType::Num)],
+            outputs: vec![param("c", PinType::Num)],
+            ..Default::default()
+        };
+        let main = FunctionProto {
+            entry: 1,
+            nodes: vec!["test.helper::main".into(), "::square".into()],
+            connections: vec![connection(0, "c", 1, "a"), connection(0, "c", 1, "b")],
+            ..Default::default()
+        };
+        let proto = ScriptProto {
+            format_version: ScriptProto::FORMAT_VERSION,
+            global_name: "test.caller".into(),
+            funcs: vec![("main".into(), main), ("square".into(), square)],
+            imports: vec!["test.helper".into()],
+            structs: Vec::new(),
+        };
+        let script = proto.clone().to_script(&env).unwrap();
+        let output = script
+            .call_func(
+                "main".into(),
+                Arc::new(env.clone()),
+                VarRegisters(HashMap::from([("a".into(), Var::Num(2.0))])),
+            )
+            .unwrap();
+        assert_eq!(output.0.get(&"c".into()), Some(&Var::Num(144.0)));
+        // calls are written back the way they were read
+        let round_trip = ScriptProto::from_script(&script);
+        let (_, main) = round_trip.funcs.iter().find(|(n, _)| n == "main").unwrap();
+        assert_eq!(main.nodes, vec!["test.helper::main", "::square"]);
+
+        let mut missing = proto;
+        missing.funcs[0].1.nodes[1] = "::cube".into();
+        assert!(missing.validate(&env).iter().any(|d| d.kind
+            == DiagnosticKind::UnknownFunction {
+                name: "::cube".into()
+            }));
+    }
+
+    #[test]
+    fn test_recursion_limit() {
+        let mut env = Environment::new();
+        env.max_call_depth = 8;
+        let proto = ScriptProto {
+            format_version: ScriptProto::FORMAT_VERSION,
+            global_name: "test.recurse".into(),
+            funcs: vec![(
+                "main".into(),
+                FunctionProto {
+                    entry: 0,
+                    nodes: vec!["::main".into()],
+                    ..Default::default()
+                },
+            )],
+            imports: Vec::new(),
+            structs: Vec::new(),
+        };
+        let script = proto.to_script(&env).unwrap();
+        let res = script.call_func("main".into(), Arc::new(env), VarRegisters::new());
+        assert!(matches!(
+            res,
+            Err(NodeError::StackOverflow { depth: 9, .. })
+        ));
+    }
+
+    const NAMED_SCRIPT_TEXT: &str = r#"
+(
+    global_name: "test.named",
+    funcs: [
+        (
+            "main", FunctionProto(
+                entry: "total",
+                nodes: {
+                    "sum": "std.math.add",
+                    "diff": "std.math.subtract",
+                    "total": "std.math.add",
+                },
+                connections: [
+                    "sum.c -> diff.b",
+                    "sum.c -> total.b",
+                    "diff.c -> total.a",
+                ],
+            )
+        )
+    ],
+)"#;
+
+    #[test]
+    fn test_named_nodes() {
+        let proto = ron::de::from_str::<ScriptProto>(NAMED_SCRIPT_TEXT).unwrap();
+        let func = &proto.funcs[0].1;
+        // ids resolve to the same indices the list format would use
+        assert_eq!(func.entry, 2);
+        assert_eq!(func.ids, vec!["sum", "diff", "total"]);
+        assert_eq!(func.connections[2].from, 1);
+        assert_eq!(func.connections[2].to, 2);
+        assert_eq!(func.connections[2].to_param, "a");
+
+        let env = Environment::new();
+        let script = proto.to_script(&env).unwrap();
+        let output = script
+            .call_func(
+                "main".into(),
+                Arc::new(env),
+                VarRegisters(HashMap::from([
+                    ("a".into(), Var::Num(3.0)),
+                    ("b".into(), Var::Num(4.0)),
+                ])),
+            )
+            .unwrap();
+        // (3 - 7) + 7
+        assert_eq!(output.0.get(&"c".into()), Some(&Var::Num(3.0)));
+
+        // ids survive being written back out
+        let text = ron::to_string(&ScriptProto::from_script(&script)).unwrap();
+        assert!(text.contains(r#""diff.c -> total.a""#));
+        assert!(text.contains(r#"entry:"total""#));
+    }
+
+    #[test]
+    fn test_named_nodes_errors() {
+        let unknown = NAMED_SCRIPT_TEXT.replace("diff.c -> total.a", "diff.c -> nope.a");
+        assert!(ron::de::from_str::<ScriptProto>(&unknown).is_err());
+        let malformed = NAMED_SCRIPT_TEXT.replace("diff.c -> total.a", "diff.c total.a");
+        assert!(ron::de::from_str::<ScriptProto>(&malformed).is_err());
+        let duplicate = NAMED_SCRIPT_TEXT.replace(r#""diff": "#, r#""sum": "#);
+        assert!(ron::de::from_str::<ScriptProto>(&duplicate).is_err());
+    }
+
+    fn run_func(proto: ScriptProto, func: &'static str) -> VarRegisters {
+        let env = Environment::new();
+        proto
+            .to_script(&env)
+            .unwrap()
+            .call_func(
+                func.into(),
+                Arc::new(env),
+                VarRegisters(HashMap::from([
+                    ("a".into(), Var::Num(3.0)),
+                    ("b".into(), Var::Num(4.0)),
+                ])),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_stabilize() {
+        let proto = ron::de::from_str::<ScriptProto>(SCRIPT_TEXT).unwrap();
+        let stable = proto.clone().stabilize();
+        let func = &stable.funcs[0].1;
+        assert_eq!(
+            func.nodes,
+            vec!["std.math.add", "std.math.add", "std.math.subtract"]
+        );
+        // the entry and every connection follow their nodes, and both connections out of node 0 keep their order
+        assert_eq!(func.entry, 1);
+        assert_eq!(
+            func.connections
+                .iter()
+                .map(|c| (c.from, c.to))
+                .collect::<Vec<_>>(),
+            vec![(0, 2), (0, 1), (2, 1)]
+        );
+        assert_eq!(run_func(proto, "func"), run_func(stable.clone(), "func"));
+
+        let text = stable.clone().to_canonical_string().unwrap();
+        assert_eq!(text, stable.stabilize().to_canonical_string().unwrap());
+    }
+
+    const ORDERED_SCRIPT_TEXT: &str = r#"
+(
+    global_name: "test.ordered",
+    funcs: [
+        (
+            "main", FunctionProto(
+                entry: "start",
+                nodes: {
+                    "start": (node: "std.control.if", constants: {"flag": Bool(true)}),
+                    "set_one": (node: "local.set.last", constants: {"value": Num(1.0)}),
+                    "set_two": (node: "local.set.last", constants: {"value": Num(2.0)}),
+                    "small": (node: "std.math.add", constants: {"a": Num(1.0), "b": Num(1.0)}),
+                    "big": (node: "std.math.add", constants: {"a": Num(5.0), "b": Num(5.0)}),
+                    "sum": (node: "std.math.add", constants: {"a": Num(0.0)}),
+                    "store": "local.set.total",
+                },
+                connections: [
+                    "start.if -> set_two.exec",
+                    "start.if -> set_one.exec",
+                    "set_one.then -> store.exec",
+                    "small.c -> sum.b",
+                    "big.c -> sum.b",
+                    "sum.c -> store.value",
+                ],
+                outputs: [(name: "last", ty: Num), (name: "total", ty: Num)],
+                locals: [(name: "last", ty: Num), (name: "total", ty: Num)],
+            )
+        )
+    ],
+)"#;
+
+    #[test]
+    fn test_stabilize_keeps_order() {
+        let proto = ScriptProto::from_ron(ORDERED_SCRIPT_TEXT).unwrap();
+        let stable = proto.clone().stabilize();
+        // set_one fires after set_two, and the last connection into sum.b wins
+        let expected = VarRegisters(HashMap::from([
+            ("last".into(), Var::Num(1.0)),
+            ("total".into(), Var::Num(10.0)),
+        ]));
+        assert_eq!(run_func(proto, "main"), expected);
+        assert_eq!(run_func(stable.clone(), "main"), expected);
+
+        let text = stable.clone().to_canonical_string().unwrap();
+        let reparsed = ScriptProto::from_ron(&text).unwrap();
+        assert_eq!(run_func(reparsed, "main"), expected);
+        assert_eq!(text, stable.stabilize().to_canonical_string().unwrap());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for text in [
+            SCRIPT_TEXT,
+            NAMED_SCRIPT_TEXT,
+            HELPER_SCRIPT_TEXT,
+            STRUCT_SCRIPT_TEXT,
+        ] {
+            let proto = ScriptProto::from_ron(text).unwrap();
+            let mut env = Environment::new();
+            proto.register_structs(&mut env).unwrap();
+            let script = proto.clone().to_script(&env).unwrap();
+            assert_eq!(
+                proto.to_canonical_string().unwrap(),
+                ScriptProto::from_script(&script)
+                    .to_canonical_string()
+                    .unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_layout() {
+        let text = NAMED_SCRIPT_TEXT.replace(
+            "            )\n        )",
+            r##"                layout: (
+                    nodes: {
+                        "total": (position: (200.0, 0.0), color: Some("#ff0000")),
+                        "sum": (position: (0.0, 0.0)),
+                    },
+                    comments: [(text: "adds it all up", position: (0.0, -50.0), size: (300.0, 40.0))],
+                    reroutes: [(from: "sum", from_param: "c", to: "total", to_param: "b", points: [(100.0, 80.0)])],
+                    groups: [(name: "maths", nodes: ["total", "diff"])],
+                ),
+            )
+        )"##,
+        );
+        let proto = ScriptProto::from_ron(&text).unwrap();
+        let layout = &proto.funcs[0].1.layout;
+        assert_eq!(layout.nodes[&2].position, (200.0, 0.0));
+        assert_eq!(layout.reroutes[0].to, 2);
+        assert_eq!(layout.groups[0].nodes, vec![2, 1]);
+
+        // the executor ignores the layout, but it survives the round trip
+        assert_eq!(run_func(proto.clone(), "main"), {
+            let plain = ron::de::from_str::<ScriptProto>(NAMED_SCRIPT_TEXT).unwrap();
+            run_func(plain, "main")
+        });
+        let env = Environment::new();
+        let script = proto.clone().to_script(&env).unwrap();
+        let written = ScriptProto::from_script(&script)
+            .to_canonical_string()
+            .unwrap();
+        assert!(written.contains(r#""total": ("#));
+        assert!(written.contains(r#"from: "sum","#));
+        assert_eq!(written, proto.clone().to_canonical_string().unwrap());
+
+        let unknown = text.replace(r#"["total", "diff"]"#, r#"["total", "nope"]"#);
+        assert!(ron::de::from_str::<ScriptProto>(&unknown).is_err());
+
+        // the list format keeps indices, which stabilize remaps along with the nodes
+        let mut indexed = proto;
+        indexed.funcs[0].1.ids.clear();
+        let stable = indexed.stabilize();
+        let layout = &stable.funcs[0].1.layout;
+        // add, subtract, add becomes add, add, subtract
+        assert_eq!(layout.nodes[&1].position, (200.0, 0.0));
+        assert_eq!(layout.reroutes[0].to, 1);
+        assert_eq!(layout.groups[0].nodes, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_migrate() {
+        // files from before versions were recorded are upgraded when read
+        let mut proto = ron::de::from_str::<ScriptProto>(SCRIPT_TEXT).unwrap();
+        assert_eq!(proto.format_version, 0);
+        assert_eq!(proto.migrate(), Ok(0));
+        assert_eq!(proto.format_version, ScriptProto::FORMAT_VERSION);
+        assert_eq!(proto.migrate(), Ok(ScriptProto::FORMAT_VERSION));
+
+        let text = proto.to_canonical_string().unwrap();
+        let version = format!("format_version: {},", ScriptProto::FORMAT_VERSION);
+        assert!(text.contains(&version));
+        assert!(ScriptProto::from_ron(&text).is_ok());
+
+        let newer = text.replace(&version, "format_version: 99,");
+        assert_eq!(
+            ScriptProto::from_ron(&newer).unwrap_err(),
+            FormatError::TooNew {
+                found: 99,
+                supported: ScriptProto::FORMAT_VERSION
+            }
+        );
+        assert!(matches!(
+            ScriptProto::from_ron("(global_name: "),
+            Err(FormatError::Parse(_))
+        ));
+
+        // versions 0 to 2 read the same as the latest
+        let old = text.replace(&version, "format_version: 2,");
+        let mut upgraded = ScriptProto::from_ron(&old).unwrap();
+        assert_eq!(upgraded.format_version, ScriptProto::FORMAT_VERSION);
+        assert_eq!(upgraded.clone().to_canonical_string().unwrap(), text);
+
+        // a missing upgrade is reported instead of skipped
+        assert_eq!(
+            upgraded.upgrade_from(ScriptProto::FORMAT_VERSION),
+            Err(FormatError::NoUpgrade {
+                from: ScriptProto::FORMAT_VERSION
+            })
+        );
+    }
+
+    const CONSTANT_SCRIPT_TEXT: &str = r#"
+(
+    global_name: "test.constants",
+    funcs: [
+        (
+            "main", FunctionProto(
+                entry: "total",
+                nodes: {
+                    "total": (node: "std.math.add", constants: {"b": Num(5.0)}),
+                    "double": "std.math.multiply",
+                },
+                connections: [
+                    "double.c -> total.a",
+                ],
+            )
+        )
+    ],
+)"#;
+
+    #[test]
+    fn test_constants() {
+        let proto = ScriptProto::from_ron(CONSTANT_SCRIPT_TEXT).unwrap();
+        let func = &proto.funcs[0].1;
+        assert_eq!(func.nodes, vec!["std.math.add", "std.math.multiply"]);
+        assert_eq!(func.constants[&0]["b"], Var::Num(5.0));
+        // inputs with a constant need no connection
+        assert!(proto
+            .validate(&Environment::new())
+            .iter()
+            .all(|d| d.node != Some(0)));
+
+        // constants are used before the blackboard, which still feeds the unconnected multiply
+        let output = run_func(proto.clone(), "main");
+        assert_eq!(output.0.get(&"c".into()), Some(&Var::Num(17.0)));
+
+        let env = Environment::new();
+        let script = proto.clone().to_script(&env).unwrap();
+        let written = ScriptProto::from_script(&script)
+            .to_canonical_string()
+            .unwrap();
+        assert!(written.contains(r#"node: "std.math.add","#));
+        assert_eq!(written, proto.clone().to_canonical_string().unwrap());
+
+        // stabilize moves constants along with their node, which sorts after "double"
+        let stable = proto.stabilize();
+        assert_eq!(stable.funcs[0].1.constants[&1]["b"], Var::Num(5.0));
+    }
+
+    #[test]
+    fn test_constant_errors() {
+        let env = Environment::new();
+        let unknown = CONSTANT_SCRIPT_TEXT.replace(r#"{"b": "#, r#"{"nope": "#);
+        let diagnostics = ScriptProto::from_ron(&unknown).unwrap().validate(&env);
+        assert!(diagnostics.contains(&Diagnostic {
+            severity: Severity::Error,
+            func: "main".into(),
+            node: Some(0),
+            kind: DiagnosticKind::UnknownConstant { pin: "nope".into() },
+        }));
+
+        let mismatch = CONSTANT_SCRIPT_TEXT.replace("Num(5.0)", r#"String("five")"#);
+        let diagnostics = ScriptProto::from_ron(&mismatch).unwrap().validate(&env);
+        assert!(diagnostics
+            .iter()
+            .any(|d| matches!(d.kind, DiagnosticKind::ConstantMismatch { .. })));
+    }
+
+    const LOCALS_SCRIPT_TEXT: &str = r#"
+(
+    global_name: "test.locals",
+    funcs: [
+        (
+            "main", FunctionProto(
+                entry: "store",
+                nodes: {
+                    "read": "local.get.total",
+                    "sum": "std.math.add",
+                    "store": "local.set.total",
+                    "more": (node: "std.math.add", constants: {"b": Num(100.0)}),
+                    "store_more": "local.set.total",
+                },
+                connections: [
+                    "read.value -> sum.b",
+                    "sum.c -> store.value",
+                    "store.then -> store_more.exec",
+                    "read.value -> more.a",
+                    "more.c -> store_more.value",
+                ],
+                outputs: [(name: "total", ty: Num)],
+                locals: [(name: "total", ty: Num, default: Some(Num(10.0)))],
+            )
+        ),
+        (
+            "count", FunctionProto(
+                entry: "store",
+                nodes: {
+                    "read": "local.get.n",
+                    "next": (node: "std.math.add", constants: {"b": Num(1.0)}),
+                    "store": "local.set.n",
+                },
+                connections: [
+                    "read.value -> next.a",
+                    "next.c -> store.value",
+                ],
+                outputs: [(name: "n", ty: Num)],
+                locals: [(name: "n", ty: Num)],
+            )
+        ),
+        (
+            "count_twice", FunctionProto(
+                entry: "sum",
+                nodes: {
+                    "first": "::count",
+                    "second": "::count",
+                    "sum": "std.math.add",
+                },
+                connections: [
+                    "first.n -> sum.a",
+                    "second.n -> sum.b",
+                ],
+            )
+        ),
+    ],
+)"#;
+
+    #[test]
+    fn test_locals() {
+        let proto = ScriptProto::from_ron(LOCALS_SCRIPT_TEXT).unwrap();
+        assert!(proto
+            .validate(&Environment::new())
+            .iter()
+            .all(|d| d.severity != Severity::Error));
+
+        // 10 + 3, then the read is refreshed for 13 + 100
+        let output = run_func(proto.clone(), "main");
+        assert_eq!(
+            output,
+            VarRegisters(HashMap::from([("total".into(), Var::Num(113.0))]))
+        );
+
+        // every call starts with its own locals
+        let output = run_func(proto.clone(), "count_twice");
+        assert_eq!(output.0.get(&"c".into()), Some(&Var::Num(2.0)));
+        // only the declared outputs of a call reach its caller
+        assert_eq!(output.0.get(&"next".into()), None);
+
+        let env = Environment::new();
+        let script = proto.clone().to_script(&env).unwrap();
+        let written = ScriptProto::from_script(&script)
+            .to_canonical_string()
+            .unwrap();
+        assert!(written.contains(r#""read": "local.get.total","#));
+        assert_eq!(written, proto.to_canonical_string().unwrap());
+    }
+
+    #[test]
+    fn test_locals_errors() {
+        let env = Environment::new();
+        let unknown = LOCALS_SCRIPT_TEXT.replace(r#""local.get.total""#, r#""local.get.nope""#);
+        let diagnostics = ScriptProto::from_ron(&unknown).unwrap().validate(&env);
+        assert!(diagnostics.contains(&Diagnostic {
+            severity: Severity::Error,
+            func: "main".into(),
+            node: Some(0),
+            kind: DiagnosticKind::UnknownLocal {
+                name: "local.get.nope".into()
+            },
+        }));
+
+        // locals are type checked like pins
+        let mismatch = LOCALS_SCRIPT_TEXT.replace(
+            r#"(name: "total", ty: Num, default: Some(Num(10.0)))"#,
+            r#"(name: "total", ty: String)"#,
+        );
+        let diagnostics = ScriptProto::from_ron(&mismatch).unwrap().validate(&env);
+        assert!(diagnostics
+            .iter()
+            .any(|d| matches!(d.kind, DiagnosticKind::TypeMismatch(_))));
+    }
+
+    #[test]
+    fn test_globals() {
+        let text = r#"
+(
+    global_name: "test.globals",
+    funcs: [
+        (
+            "main", FunctionProto(
+                entry: "store",
+                nodes: {
+                    "read": (node: "std.vars.get", constants: {"name": String("score")}),
+                    "sum": (node: "std.math.add", constants: {"b": Num(1.0)}),
+                    "store": (node: "std.vars.set", constants: {"name": String("score")}),
+                    "store_again": (node: "std.vars.set", constants: {"name": String("score")}),
+                    "more": (node: "std.math.add", constants: {"b": Num(10.0)}),
+                },
+                connections: [
+                    "read.value -> sum.a",
+                    "sum.c -> store.value",
+                    "store.then -> store_again.exec",
+                    "read.value -> more.a",
+                    "more.c -> store_again.value",
+                ],
+            )
+        ),
+    ],
+)"#;
+        let proto = ScriptProto::from_ron(text).unwrap();
+        let mut env = Environment::new();
+        env.globals = Globals::new(VarRegisters(HashMap::from([(
+            "score".into(),
+            Var::Num(1.0),
+        )])));
+        let script = proto.to_script(&env).unwrap();
+        script
+            .call_func("main".into(), Arc::new(env.clone()), VarRegisters::new())
+            .unwrap();
+        // the read is evaluated again after the first write: (1 + 1) + 10
+        assert_eq!(env.globals.get(&"score".into()), Some(Var::Num(12.0)));
     }
 }
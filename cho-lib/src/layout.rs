@@ -0,0 +1,123 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// A point on the editor canvas
+pub type Position = (f32, f32);
+
+/// How a function is drawn in an editor. Nodes are referred to by `N`, an index in memory or an id in files. Nothing here affects execution
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Layout<N: Ord = usize> {
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub nodes: BTreeMap<N, NodeLayout>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub comments: Vec<Comment>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub reroutes: Vec<Reroute<N>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<Group<N>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NodeLayout {
+    pub position: Position,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub collapsed: bool,
+}
+
+/// Free text placed on the canvas
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Comment {
+    pub text: String,
+    pub position: Position,
+    pub size: Position,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+/// Points a connection is drawn through on its way between two pins
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reroute<N> {
+    pub from: N,
+    pub from_param: String,
+    pub to: N,
+    pub to_param: String,
+    pub points: Vec<Position>,
+}
+
+/// A named frame around a set of nodes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Group<N> {
+    pub name: String,
+    pub nodes: Vec<N>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+impl<N: Ord> Default for Layout<N> {
+    fn default() -> Self {
+        Self {
+            nodes: BTreeMap::new(),
+            comments: Vec::new(),
+            reroutes: Vec::new(),
+            groups: Vec::new(),
+        }
+    }
+}
+
+impl<N: Ord> Layout<N> {
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+            && self.comments.is_empty()
+            && self.reroutes.is_empty()
+            && self.groups.is_empty()
+    }
+
+    /// Rewrites every node reference with `f`, failing on the first reference it rejects
+    pub fn map_nodes<M: Ord, E>(
+        self,
+        mut f: impl FnMut(N) -> Result<M, E>,
+    ) -> Result<Layout<M>, E> {
+        Ok(Layout {
+            nodes: self
+                .nodes
+                .into_iter()
+                .map(|(node, layout)| Ok((f(node)?, layout)))
+                .collect::<Result<_, E>>()?,
+            comments: self.comments,
+            reroutes: self
+                .reroutes
+                .into_iter()
+                .map(|reroute| {
+                    Ok(Reroute {
+                        from: f(reroute.from)?,
+                        from_param: reroute.from_param,
+                        to: f(reroute.to)?,
+                        to_param: reroute.to_param,
+                        points: reroute.points,
+                    })
+                })
+                .collect::<Result<_, E>>()?,
+            groups: self
+                .groups
+                .into_iter()
+                .map(|group| {
+                    Ok(Group {
+                        name: group.name,
+                        nodes: group
+                            .nodes
+                            .into_iter()
+                            .map(&mut f)
+                            .collect::<Result<_, E>>()?,
+                        color: group.color,
+                    })
+                })
+                .collect::<Result<_, E>>()?,
+        })
+    }
+}
@@ -3,26 +3,46 @@ use std::env;
 use logger::Logger;
 use nodes::Node;
 use scripts::Script;
-use types::{StringName, TypeRegistry};
+use structs::StructDef;
+use types::{Globals, NamespacedType, RegistryError, RegistryPolicy, StringName, TypeRegistry};
 
 #[cfg(feature = "stdlib")]
 pub mod stdlib;
 
 pub mod filetype;
+pub mod layout;
 pub mod logger;
 pub mod nodes;
+pub mod plan;
 pub mod scripts;
+pub mod structs;
 pub mod types;
 
+/// Environment flag which logs every executed node, at a considerable cost to execution speed
+pub const FLAG_DEBUG: &str = "CHO_DEBUG";
+
 #[derive(Debug, Clone)]
 pub struct Environment {
     pub flags: Vec<StringName>,
     pub nodes: TypeRegistry<Node>,
     pub scripts: TypeRegistry<Script>,
+    pub structs: TypeRegistry<StructDef>,
     pub logger: Logger,
+    /// Variables of the current execution, read and written by the `std.vars` nodes. Clones of the environment share them
+    pub globals: Globals,
+    /// How deeply function calls may nest before execution fails, which stops runaway recursion
+    pub max_call_depth: usize,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Environment {
+    pub const DEFAULT_MAX_CALL_DEPTH: usize = 64;
+
     pub fn new() -> Self {
         let mut cho_env = Self::new_empty();
 
@@ -31,6 +51,9 @@ impl Environment {
                 cho_env.flags.push(key.into());
             }
         }
+        if cho_env.flags.contains(&FLAG_DEBUG.into()) {
+            cho_env.set_log_level(log::LevelFilter::Trace);
+        }
         if cfg!(feature = "stdlib") {
             stdlib::register(&mut cho_env.nodes);
         }
@@ -38,11 +61,85 @@ impl Environment {
     }
 
     pub fn new_empty() -> Self {
+        let logger = Logger::new("choreoghrapher.log".into());
+        let mut nodes = TypeRegistry::default();
+        let mut scripts = TypeRegistry::default();
+        let mut structs = TypeRegistry::default();
+        nodes.set_logger(logger.clone());
+        scripts.set_logger(logger.clone());
+        structs.set_logger(logger.clone());
         Self {
             flags: Vec::new(),
-            nodes: TypeRegistry::default(),
-            scripts: TypeRegistry::default(),
-            logger: Logger::new("choreoghrapher.log".into()),
+            nodes,
+            scripts,
+            structs,
+            logger,
+            globals: Globals::default(),
+            max_call_depth: Self::DEFAULT_MAX_CALL_DEPTH,
+        }
+    }
+
+    /// Logs messages up to `level`, both from the environment and from its registries
+    pub fn set_log_level(&mut self, level: log::LevelFilter) {
+        self.logger.set_level(level);
+        self.nodes.set_logger(self.logger.clone());
+        self.scripts.set_logger(self.logger.clone());
+        self.structs.set_logger(self.logger.clone());
+    }
+
+    /// Registers a struct type along with its generated `make`, `break`, `get_field` and `set_field` nodes
+    ///
+    /// Nothing is registered if the struct or any of its nodes would be refused
+    pub fn register_struct(&mut self, def: StructDef) -> Result<(), RegistryError> {
+        let nodes = def.generate_nodes();
+        // only rejecting registries can fail, so checking up front keeps the environment whole
+        if self.structs.policy() == RegistryPolicy::Reject && self.structs.contains(&def.name) {
+            return Err(RegistryError::Duplicate(def.name.clone()));
         }
+        if self.nodes.policy() == RegistryPolicy::Reject {
+            if let Some(node) = nodes
+                .iter()
+                .find(|node| self.nodes.contains(&node.get_name()))
+            {
+                return Err(RegistryError::Duplicate(node.get_name()));
+            }
+        }
+        self.structs.try_register(def)?;
+        for node in nodes {
+            self.nodes.try_register(node)?;
+        }
+        Ok(())
+    }
+
+    /// Registers nodes on top of the existing ones, e.g. a user library over [stdlib::register].
+    ///
+    /// Existing nodes of the same name are shadowed with a warning, and come back if their replacement is unregistered
+    pub fn layer_nodes(&mut self, nodes: impl IntoIterator<Item = Node>) {
+        let policy = self.nodes.policy();
+        self.nodes.set_policy(RegistryPolicy::ShadowWithWarning);
+        for node in nodes {
+            // shadowing never rejects a node
+            let _ = self.nodes.try_register(node);
+        }
+        self.nodes.set_policy(policy);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use log::{Level, LevelFilter};
+
+    use crate::Environment;
+
+    #[test]
+    fn test_log_level() {
+        let mut env = Environment::new_empty();
+        assert!(!env.logger.enabled(Level::Debug));
+        env.set_log_level(LevelFilter::Debug);
+        // clones handed to executions keep the level
+        let env = env.clone();
+        assert!(env.logger.enabled(Level::Debug));
+        assert!(!env.logger.enabled(Level::Trace));
+        assert!(log::max_level() >= LevelFilter::Debug);
     }
 }
@@ -1,11 +1,19 @@
-use std::{fs::OpenOptions, io::Write, path::PathBuf, sync::RwLock};
+use std::{
+    fs::File,
+    io::{LineWriter, Write},
+    path::PathBuf,
+    sync::{Arc, Mutex, RwLock},
+};
 
 use chrono::Local;
-use log::{debug, error, info, trace, warn};
+use log::{debug, error, info, trace, warn, Level, LevelFilter};
 
 #[derive(Debug, Clone)]
 pub struct Logger {
-    file: PathBuf,
+    /// Opened once and shared by every clone of the logger
+    file: Option<Arc<Mutex<LineWriter<File>>>>,
+    /// Messages above this level are dropped without being written anywhere
+    level: LevelFilter,
 }
 
 impl Logger {
@@ -27,44 +35,77 @@ impl Logger {
             }
         }
 
+        // creating the file purges old data from last logging session
         #[cfg(not(test))]
-        {
-            use std::fs::File;
-            if let Ok(mut fout) = File::create(file.clone()) {
-                // purges old data from last logging session
-                if let Err(e) = fout.write("".as_bytes()) {
-                    eprintln!("Failed to clear previous file {} :: {}", file.display(), e);
-                }
+        let file = match File::create(&file) {
+            Ok(fout) => Some(Arc::new(Mutex::new(LineWriter::new(fout)))),
+            Err(e) => {
+                eprintln!("Failed to clear previous file {} :: {}", file.display(), e);
+                None
             }
-        }
+        };
         #[cfg(test)]
-        {
-            log::set_max_level(log::LevelFilter::Debug);
+        let file = {
+            log::set_max_level(LevelFilter::Debug);
+            drop(file);
+            None
+        };
+        Self {
+            file,
+            level: LevelFilter::Info,
         }
-        Self { file }
+    }
+
+    /// Lets through messages up to `level`. Debug and trace messages are dropped by default, since they are logged for every executed node
+    pub fn set_level(&mut self, level: LevelFilter) {
+        self.level = level;
+        // the console only shows what the `log` facade lets through
+        if level > log::max_level() {
+            log::set_max_level(level);
+        }
+    }
+
+    /// Whether messages of `level` are logged. Check this before formatting anything costly
+    pub fn enabled(&self, level: Level) -> bool {
+        level <= self.level
     }
 
     pub fn error(&self, msg: impl Into<String>) {
+        if !self.enabled(Level::Error) {
+            return;
+        }
         let s: String = msg.into();
         self.emit_to_file(&format!("[E] {:} {s}\n", Local::now()));
         error!("{s}");
     }
     pub fn warn(&self, msg: impl Into<String>) {
+        if !self.enabled(Level::Warn) {
+            return;
+        }
         let s: String = msg.into();
         self.emit_to_file(&format!("[W] {:} {s}\n", Local::now()));
         warn!("{s}");
     }
     pub fn info(&self, msg: impl Into<String>) {
+        if !self.enabled(Level::Info) {
+            return;
+        }
         let s: String = msg.into();
         self.emit_to_file(&format!("[*] {:} {s}\n", Local::now()));
         info!("{s}");
     }
     pub fn debug(&self, msg: impl Into<String>) {
+        if !self.enabled(Level::Debug) {
+            return;
+        }
         let s: String = msg.into();
         self.emit_to_file(&format!("[D] {:} {s}\n", Local::now()));
         debug!("{s}");
     }
     pub fn trace(&self, msg: impl Into<String>) {
+        if !self.enabled(Level::Trace) {
+            return;
+        }
         let s: String = msg.into();
         self.emit_to_file(&format!("[T] {:} {s}\n", Local::now()));
         trace!("{s}");
@@ -75,15 +116,10 @@ impl Logger {
             eprintln!("{msg}");
             return;
         }
-        let Ok(mut file) = OpenOptions::new()
-            .write(true)
-            .append(true)
-            .create(true)
-            .open(self.file.clone())
-        else {
+        let Some(Ok(mut file)) = self.file.as_ref().map(|file| file.lock()) else {
             return;
         };
-        match file.write(msg.as_bytes()) {
+        match file.write_all(msg.as_bytes()) {
             Ok(_) => (),
             Err(e) => eprintln!("failed to write to log file: {}", e),
         }
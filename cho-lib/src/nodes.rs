@@ -1,15 +1,42 @@
-use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use log::Level;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    scripts::Script,
-    types::{GlobalName, NamespacedType, StringName, Var, VarRegisters},
+    scripts::{Function, Param, Script},
+    types::{
+        GlobalName, NamespacedType, PinRegisters, PinType, RegisterLayout, SlotRegisters,
+        StringName, Var, VarRegisters,
+    },
     Environment,
 };
 
+/// Reserved output pin. When fired, the exec pins fired alongside it run as a loop body, after which the node is executed again with its previous outputs added to its inputs
+pub const FLOW_LOOP: &str = "__loop";
+/// Reserved pin. As an output it stops the innermost loop, as an input it tells a loop node that its body requested a break
+pub const FLOW_BREAK: &str = "__break";
+/// Reserved output pin. Skips the rest of the current iteration of the innermost loop
+pub const FLOW_CONTINUE: &str = "__continue";
+/// Reserved output pin. Where a loop node left off, handed back to it on the next iteration
+pub const FLOW_CURSOR: &str = "__cursor";
+
+/// Reserved pins carry control flow and loop state. They never reach the outputs of a function
+pub fn is_reserved_pin(pin: &str) -> bool {
+    pin.starts_with("__")
+}
+
+// nodes are shared behind an Arc once registered, so the size of the basic variant does not matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, PartialEq)]
 pub enum Node {
     Basic(BasicNode),
     Script(ScriptNode),
+    Call(CallNode),
+    Local(LocalNode),
 }
 
 #[derive(Debug, Clone)]
@@ -32,19 +59,78 @@ pub enum NodeError {
         received: Var,
         msg: String,
     },
+    /// Checked integer arithmetic left the range of [Var::Int]
+    Overflow {
+        name: GlobalName,
+        msg: String,
+    },
+    /// Function calls nested deeper than [Environment::max_call_depth]
+    StackOverflow {
+        name: GlobalName,
+        depth: usize,
+    },
 }
 
+type NamedLogic =
+    dyn Fn(Arc<Environment>, VarRegisters) -> Result<VarRegisters, NodeError> + Send + Sync;
+type SlotLogic =
+    dyn Fn(Arc<Environment>, &SlotRegisters) -> Result<VarRegisters, NodeError> + Send + Sync;
+
+/// What a [BasicNode] runs. Build it with [BasicNodeLogic::new], or [BasicNodeLogic::slots] for nodes on the hot path
 #[derive(Clone)]
-pub struct BasicNodeLogic(
-    pub Arc<dyn Fn(Arc<Environment>, VarRegisters) -> Result<VarRegisters, NodeError>>,
-);
+pub enum BasicNodeLogic {
+    /// Reads its inputs by name
+    Named(Arc<NamedLogic>),
+    /// Reads its inputs by slot, laid out by [NodeData::input_layout]. Cheaper for small nodes which run often
+    Slots(Arc<SlotLogic>),
+}
 
 #[derive(Clone)]
 pub struct BasicNode {
     pub name: GlobalName,
-    pub inputs: VarRegisters,
-    pub outputs: VarRegisters,
+    pub inputs: PinRegisters,
+    pub outputs: PinRegisters,
     pub logic: BasicNodeLogic,
+    pub meta: NodeMeta,
+    /// See [NodeData::is_volatile]
+    pub volatile: bool,
+}
+
+/// Presentation data for editors and generated docs. Apart from the pin defaults of basic nodes, none of it affects execution
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NodeMeta {
+    /// Shown instead of the type name when set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    /// Palette section the node is listed under, e.g. `math`
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub category: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Documentation of input and output pins by name
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub pins: BTreeMap<String, PinMeta>,
+    /// Why the node should no longer be used and what to use instead
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    /// Colour hint for the node header, e.g. `#4a90d9`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PinMeta {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    /// Value an editor should suggest for an unconnected input. Basic nodes use it themselves when the input is left empty
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<Var>,
 }
 
 #[derive(Clone)]
@@ -54,6 +140,37 @@ pub struct ScriptNode {
     pub script: Arc<Script>,
 }
 
+/// Calls a function by name, written `other.script::func` in a script file, or `::func` for a function of the calling script.
+///
+/// Unlike a [ScriptNode] the script is only looked up in [Environment::scripts] when the call runs, so functions can call themselves and each other
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallNode {
+    /// The script to call into, or `None` for the calling script
+    pub script: Option<GlobalName>,
+    pub func: StringName,
+    /// Signature of the called function when the call was linked
+    pub inputs: Vec<Param>,
+    pub outputs: Vec<Param>,
+}
+
+/// Reads or writes a variable declared on the function it is used in, written `local.get.name` or `local.set.name` in a script file.
+///
+/// The variables live on the call frame, so only the executor can run these nodes
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalNode {
+    pub name: StringName,
+    pub ty: PinType,
+    pub access: LocalAccess,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocalAccess {
+    /// Pure, producing the current value on `value`
+    Get,
+    /// Stores `value` when `exec` fires, then fires `then` and passes the value on
+    Set,
+}
+
 pub trait NodeData {
     fn execute(
         &self,
@@ -62,6 +179,38 @@ pub trait NodeData {
     ) -> Result<VarRegisters, NodeError>;
     fn get_inputs(&self) -> Vec<StringName>;
     fn get_outputs(&self) -> Vec<StringName>;
+    fn get_input_types(&self) -> PinRegisters;
+    fn get_output_types(&self) -> PinRegisters;
+    /// The subset of [NodeData::get_inputs] which receive execution flow rather than data
+    fn get_exec_inputs(&self) -> Vec<StringName>;
+    /// The subset of [NodeData::get_outputs] which drive execution flow rather than data
+    fn get_exec_outputs(&self) -> Vec<StringName>;
+
+    fn get_meta(&self) -> NodeMeta;
+
+    /// Pure nodes have no execution inputs, so they are only evaluated when another node pulls on their data
+    fn is_pure(&self) -> bool {
+        self.get_exec_inputs().is_empty()
+    }
+
+    /// Volatile nodes are pure, but read state which other nodes change. They are evaluated again every time their data is pulled on, rather than once
+    fn is_volatile(&self) -> bool {
+        false
+    }
+
+    /// Slots of the inputs handed to [NodeData::execute_slots]
+    fn input_layout(&self) -> RegisterLayout {
+        RegisterLayout::new(self.get_inputs())
+    }
+
+    /// Executes the node with inputs laid out by [NodeData::input_layout], or a layout starting with it. Falls back to [NodeData::execute] by name
+    fn execute_slots(
+        &self,
+        env: Arc<Environment>,
+        inputs: &SlotRegisters,
+    ) -> Result<VarRegisters, NodeError> {
+        self.execute(env, inputs.to_registers())
+    }
 }
 
 impl PartialEq for BasicNode {
@@ -82,7 +231,24 @@ impl NodeData for BasicNode {
         env: Arc<Environment>,
         inputs: VarRegisters,
     ) -> Result<VarRegisters, NodeError> {
-        self.logic.0(env, inputs)
+        match &self.logic {
+            BasicNodeLogic::Named(logic) => logic(env, inputs),
+            BasicNodeLogic::Slots(logic) => {
+                let layout = Arc::new(self.input_layout());
+                logic(env, &SlotRegisters::from_registers(layout, inputs))
+            }
+        }
+    }
+
+    fn execute_slots(
+        &self,
+        env: Arc<Environment>,
+        inputs: &SlotRegisters,
+    ) -> Result<VarRegisters, NodeError> {
+        match &self.logic {
+            BasicNodeLogic::Named(logic) => logic(env, inputs.to_registers()),
+            BasicNodeLogic::Slots(logic) => logic(env, inputs),
+        }
     }
 
     fn get_inputs(&self) -> Vec<StringName> {
@@ -92,6 +258,247 @@ impl NodeData for BasicNode {
     fn get_outputs(&self) -> Vec<StringName> {
         self.outputs.0.keys().cloned().collect()
     }
+
+    fn get_input_types(&self) -> PinRegisters {
+        self.inputs.clone()
+    }
+
+    fn get_output_types(&self) -> PinRegisters {
+        self.outputs.clone()
+    }
+
+    fn get_exec_inputs(&self) -> Vec<StringName> {
+        self.inputs.exec_pins()
+    }
+
+    fn get_exec_outputs(&self) -> Vec<StringName> {
+        self.outputs.exec_pins()
+    }
+
+    fn get_meta(&self) -> NodeMeta {
+        self.meta.clone()
+    }
+
+    fn is_volatile(&self) -> bool {
+        self.volatile
+    }
+}
+
+impl ScriptNode {
+    /// The function this node calls
+    pub fn function(&self) -> Option<&Function> {
+        self.script.funcs.get(&self.func)
+    }
+}
+
+impl CallNode {
+    pub const DELIM: &str = "::";
+
+    /// Parses `other.script::func` or `::func`, returning `None` for names which are not calls
+    pub fn parse_path(path: &str) -> Option<(Option<GlobalName>, StringName)> {
+        let (script, func) = path.split_once(Self::DELIM)?;
+        let script = match script.is_empty() {
+            true => None,
+            false => Some(GlobalName::from_path(script)),
+        };
+        Some((script, func.to_string().into()))
+    }
+
+    /// The name of this call as written in a script file
+    pub fn to_path(&self) -> String {
+        let script = self
+            .script
+            .as_ref()
+            .map(GlobalName::to_path)
+            .unwrap_or_default();
+        format!("{}{}{}", script, Self::DELIM, self.func)
+    }
+}
+
+impl NamespacedType for CallNode {
+    fn get_name(&self) -> GlobalName {
+        GlobalName::from_path(self.to_path())
+    }
+}
+
+impl NodeData for CallNode {
+    /// Only calls into other scripts can run outside of a script, since there is no calling script to look functions up on
+    fn execute(
+        &self,
+        env: Arc<Environment>,
+        inputs: VarRegisters,
+    ) -> Result<VarRegisters, NodeError> {
+        let Some(name) = &self.script else {
+            return Err(NodeError::Unhandled(format!(
+                "Cannot call {:?} outside of its script",
+                self.to_path()
+            )));
+        };
+        let Some(script) = env.scripts.get(name) else {
+            return Err(NodeError::TypeNotFound {
+                name: name.clone(),
+                msg: format!("No script registered for call to {:?}", self.to_path()),
+            });
+        };
+        script.call_func(self.func.clone(), env, inputs)
+    }
+
+    fn get_inputs(&self) -> Vec<StringName> {
+        self.inputs.iter().map(|param| param.name.clone()).collect()
+    }
+
+    fn get_outputs(&self) -> Vec<StringName> {
+        self.outputs
+            .iter()
+            .map(|param| param.name.clone())
+            .collect()
+    }
+
+    fn get_input_types(&self) -> PinRegisters {
+        Param::registers(&self.inputs)
+    }
+
+    fn get_output_types(&self) -> PinRegisters {
+        Param::registers(&self.outputs)
+    }
+
+    fn get_exec_inputs(&self) -> Vec<StringName> {
+        self.get_input_types().exec_pins()
+    }
+
+    fn get_exec_outputs(&self) -> Vec<StringName> {
+        self.get_output_types().exec_pins()
+    }
+
+    fn get_meta(&self) -> NodeMeta {
+        NodeMeta::new(format!("Calls {}", self.to_path())).with_category("call")
+    }
+}
+
+impl LocalNode {
+    pub const GET: &str = "local.get";
+    pub const SET: &str = "local.set";
+
+    /// Parses `local.get.name` or `local.set.name`, returning `None` for names which are not local variable accesses
+    pub fn parse_path(path: &str) -> Option<(LocalAccess, StringName)> {
+        let (access, name) = path.rsplit_once(GlobalName::PATH_DELIM)?;
+        let access = match access {
+            Self::GET => LocalAccess::Get,
+            Self::SET => LocalAccess::Set,
+            _ => return None,
+        };
+        Some((access, name.to_string().into()))
+    }
+
+    /// The name of this access as written in a script file
+    pub fn to_path(&self) -> String {
+        let access = match self.access {
+            LocalAccess::Get => Self::GET,
+            LocalAccess::Set => Self::SET,
+        };
+        format!("{}{}{}", access, GlobalName::PATH_DELIM, self.name)
+    }
+}
+
+impl NamespacedType for LocalNode {
+    fn get_name(&self) -> GlobalName {
+        GlobalName::from_path(self.to_path())
+    }
+}
+
+impl NodeData for LocalNode {
+    fn execute(
+        &self,
+        _env: Arc<Environment>,
+        _inputs: VarRegisters,
+    ) -> Result<VarRegisters, NodeError> {
+        Err(NodeError::Unhandled(format!(
+            "Cannot access local {:?} outside of a function",
+            self.name
+        )))
+    }
+
+    fn get_inputs(&self) -> Vec<StringName> {
+        self.get_input_types().0.into_keys().collect()
+    }
+
+    fn get_outputs(&self) -> Vec<StringName> {
+        self.get_output_types().0.into_keys().collect()
+    }
+
+    fn get_input_types(&self) -> PinRegisters {
+        match self.access {
+            LocalAccess::Get => PinRegisters::default(),
+            LocalAccess::Set => PinRegisters(HashMap::from([
+                ("exec".into(), PinType::Exec),
+                ("value".into(), self.ty.clone()),
+            ])),
+        }
+    }
+
+    fn get_output_types(&self) -> PinRegisters {
+        match self.access {
+            LocalAccess::Get => PinRegisters(HashMap::from([("value".into(), self.ty.clone())])),
+            LocalAccess::Set => PinRegisters(HashMap::from([
+                ("then".into(), PinType::Exec),
+                ("value".into(), self.ty.clone()),
+            ])),
+        }
+    }
+
+    fn get_exec_inputs(&self) -> Vec<StringName> {
+        self.get_input_types().exec_pins()
+    }
+
+    fn get_exec_outputs(&self) -> Vec<StringName> {
+        self.get_output_types().exec_pins()
+    }
+
+    fn get_meta(&self) -> NodeMeta {
+        let desc = match self.access {
+            LocalAccess::Get => format!("Reads the local variable {}", self.name),
+            LocalAccess::Set => format!("Writes the local variable {}", self.name),
+        };
+        NodeMeta::new(desc).with_category("local")
+    }
+
+    fn is_volatile(&self) -> bool {
+        self.access == LocalAccess::Get
+    }
+}
+
+impl Node {
+    /// Whether an input falls back to a declared default when nothing provides it
+    pub fn has_default(&self, pin: &StringName) -> bool {
+        let params = match self {
+            Node::Basic(basic) => {
+                return basic
+                    .meta
+                    .pins
+                    .get(pin.as_str())
+                    .is_some_and(|meta| meta.default.is_some())
+            }
+            Node::Local(_) => return false,
+            Node::Script(script) => match script.function() {
+                Some(func) => &func.inputs,
+                None => return false,
+            },
+            Node::Call(call) => &call.inputs,
+        };
+        params
+            .iter()
+            .any(|param| param.name == *pin && param.default.is_some())
+    }
+
+    /// Whether the pins of this node are known. Functions without a declared signature accept any pin
+    pub fn declares_pins(&self) -> bool {
+        match self {
+            Node::Basic(_) => true,
+            Node::Script(script) => script.function().is_some_and(Function::has_signature),
+            Node::Call(call) => !call.inputs.is_empty() || !call.outputs.is_empty(),
+            Node::Local(_) => true,
+        }
+    }
 }
 
 impl NamespacedType for ScriptNode {
@@ -106,19 +513,52 @@ impl NodeData for ScriptNode {
         env: Arc<Environment>,
         inputs: VarRegisters,
     ) -> Result<VarRegisters, NodeError> {
-        env.logger
-            .debug(format!("Executing script node: {:?}", self.name));
+        if env.logger.enabled(Level::Debug) {
+            env.logger
+                .debug(format!("Executing script node: {:?}", self.name));
+        }
 
-        self.script
-            .call_func(self.func.clone(), env, inputs, &mut Vec::new())
+        self.script.call_func(self.func.clone(), env, inputs)
     }
 
     fn get_inputs(&self) -> Vec<StringName> {
-        Vec::new()
+        self.get_input_types().0.into_keys().collect()
     }
 
     fn get_outputs(&self) -> Vec<StringName> {
-        Vec::new()
+        self.get_output_types().0.into_keys().collect()
+    }
+
+    fn get_input_types(&self) -> PinRegisters {
+        self.function()
+            .map(Function::input_types)
+            .unwrap_or_default()
+    }
+
+    fn get_output_types(&self) -> PinRegisters {
+        self.function()
+            .map(Function::output_types)
+            .unwrap_or_default()
+    }
+
+    fn get_exec_inputs(&self) -> Vec<StringName> {
+        self.get_input_types().exec_pins()
+    }
+
+    fn get_exec_outputs(&self) -> Vec<StringName> {
+        self.get_output_types().exec_pins()
+    }
+
+    /// Taken from the function in the script file, listed under the script's namespace unless it names a category
+    fn get_meta(&self) -> NodeMeta {
+        let mut meta = self
+            .function()
+            .map(|func| func.meta.clone())
+            .unwrap_or_default();
+        if meta.category.is_empty() {
+            meta.category = self.script.name.0 .0.to_string();
+        }
+        meta
     }
 }
 
@@ -127,6 +567,8 @@ impl NamespacedType for Node {
         match self {
             Node::Basic(basic_node) => basic_node.get_name(),
             Node::Script(script_node) => script_node.get_name(),
+            Node::Call(call_node) => call_node.get_name(),
+            Node::Local(local_node) => local_node.get_name(),
         }
     }
 }
@@ -140,6 +582,8 @@ impl NodeData for Node {
         match self {
             Node::Basic(basic_node) => basic_node.execute(env, inputs),
             Node::Script(script_node) => script_node.execute(env, inputs),
+            Node::Call(call_node) => call_node.execute(env, inputs),
+            Node::Local(local_node) => local_node.execute(env, inputs),
         }
     }
 
@@ -147,6 +591,8 @@ impl NodeData for Node {
         match self {
             Node::Basic(basic_node) => basic_node.get_inputs(),
             Node::Script(script_node) => script_node.get_inputs(),
+            Node::Call(call_node) => call_node.get_inputs(),
+            Node::Local(local_node) => local_node.get_inputs(),
         }
     }
 
@@ -154,10 +600,148 @@ impl NodeData for Node {
         match self {
             Node::Basic(basic_node) => basic_node.get_outputs(),
             Node::Script(script_node) => script_node.get_outputs(),
+            Node::Call(call_node) => call_node.get_outputs(),
+            Node::Local(local_node) => local_node.get_outputs(),
+        }
+    }
+
+    fn get_input_types(&self) -> PinRegisters {
+        match self {
+            Node::Basic(basic_node) => basic_node.get_input_types(),
+            Node::Script(script_node) => script_node.get_input_types(),
+            Node::Call(call_node) => call_node.get_input_types(),
+            Node::Local(local_node) => local_node.get_input_types(),
+        }
+    }
+
+    fn get_output_types(&self) -> PinRegisters {
+        match self {
+            Node::Basic(basic_node) => basic_node.get_output_types(),
+            Node::Script(script_node) => script_node.get_output_types(),
+            Node::Call(call_node) => call_node.get_output_types(),
+            Node::Local(local_node) => local_node.get_output_types(),
+        }
+    }
+
+    fn get_exec_inputs(&self) -> Vec<StringName> {
+        match self {
+            Node::Basic(basic_node) => basic_node.get_exec_inputs(),
+            Node::Script(script_node) => script_node.get_exec_inputs(),
+            Node::Call(call_node) => call_node.get_exec_inputs(),
+            Node::Local(local_node) => local_node.get_exec_inputs(),
+        }
+    }
+
+    fn get_exec_outputs(&self) -> Vec<StringName> {
+        match self {
+            Node::Basic(basic_node) => basic_node.get_exec_outputs(),
+            Node::Script(script_node) => script_node.get_exec_outputs(),
+            Node::Call(call_node) => call_node.get_exec_outputs(),
+            Node::Local(local_node) => local_node.get_exec_outputs(),
+        }
+    }
+
+    fn get_meta(&self) -> NodeMeta {
+        match self {
+            Node::Basic(basic_node) => basic_node.get_meta(),
+            Node::Script(script_node) => script_node.get_meta(),
+            Node::Call(call_node) => call_node.get_meta(),
+            Node::Local(local_node) => local_node.get_meta(),
+        }
+    }
+
+    fn is_volatile(&self) -> bool {
+        match self {
+            Node::Basic(basic_node) => basic_node.is_volatile(),
+            Node::Script(script_node) => script_node.is_volatile(),
+            Node::Call(call_node) => call_node.is_volatile(),
+            Node::Local(local_node) => local_node.is_volatile(),
+        }
+    }
+
+    fn input_layout(&self) -> RegisterLayout {
+        match self {
+            Node::Basic(basic_node) => basic_node.input_layout(),
+            Node::Script(script_node) => script_node.input_layout(),
+            Node::Call(call_node) => call_node.input_layout(),
+            Node::Local(local_node) => local_node.input_layout(),
+        }
+    }
+
+    fn execute_slots(
+        &self,
+        env: Arc<Environment>,
+        inputs: &SlotRegisters,
+    ) -> Result<VarRegisters, NodeError> {
+        match self {
+            Node::Basic(basic_node) => basic_node.execute_slots(env, inputs),
+            Node::Script(script_node) => script_node.execute_slots(env, inputs),
+            Node::Call(call_node) => call_node.execute_slots(env, inputs),
+            Node::Local(local_node) => local_node.execute_slots(env, inputs),
         }
     }
 }
 
+impl NodeMeta {
+    pub fn new(description: impl Into<String>) -> Self {
+        Self {
+            description: description.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_display_name(mut self, display_name: impl Into<String>) -> Self {
+        self.display_name = Some(display_name.into());
+        self
+    }
+
+    pub fn with_category(mut self, category: impl Into<String>) -> Self {
+        self.category = category.into();
+        self
+    }
+
+    pub fn with_tags(mut self, tags: impl IntoIterator<Item = &'static str>) -> Self {
+        self.tags.extend(tags.into_iter().map(String::from));
+        self
+    }
+
+    pub fn with_pin(mut self, pin: impl Into<String>, description: impl Into<String>) -> Self {
+        self.pins.entry(pin.into()).or_default().description = description.into();
+        self
+    }
+
+    pub fn with_pin_default(
+        mut self,
+        pin: impl Into<String>,
+        description: impl Into<String>,
+        default: Var,
+    ) -> Self {
+        let pin_meta = self.pins.entry(pin.into()).or_default();
+        pin_meta.description = description.into();
+        pin_meta.default = Some(default);
+        self
+    }
+
+    pub fn with_deprecated(mut self, notice: impl Into<String>) -> Self {
+        self.deprecated = Some(notice.into());
+        self
+    }
+
+    pub fn with_icon(mut self, icon: impl Into<String>) -> Self {
+        self.icon = Some(icon.into());
+        self
+    }
+
+    pub fn with_color(mut self, color: impl Into<String>) -> Self {
+        self.color = Some(color.into());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
 impl std::fmt::Debug for BasicNode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("BasicNode")
@@ -183,9 +767,23 @@ impl PartialEq for ScriptNode {
 }
 
 impl BasicNodeLogic {
+    /// Logic reading its inputs by name. It has to be `Send + Sync`, since nodes are shared between threads through the [Environment]
     pub fn new(
-        func_ref: impl Fn(Arc<Environment>, VarRegisters) -> Result<VarRegisters, NodeError> + 'static,
+        func_ref: impl Fn(Arc<Environment>, VarRegisters) -> Result<VarRegisters, NodeError>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        Self::Named(Arc::new(func_ref))
+    }
+
+    /// Logic reading its inputs by slot, see [BasicNodeLogic::Slots]
+    pub fn slots(
+        func_ref: impl Fn(Arc<Environment>, &SlotRegisters) -> Result<VarRegisters, NodeError>
+            + Send
+            + Sync
+            + 'static,
     ) -> Self {
-        Self(Arc::new(func_ref))
+        Self::Slots(Arc::new(func_ref))
     }
 }
@@ -0,0 +1,287 @@
+use std::sync::Arc;
+
+use crate::{
+    nodes::NodeData,
+    scripts::Function,
+    types::{RegisterLayout, StringName, Var},
+};
+
+/// Everything the interpreter needs to know about the shape of a [Function], worked out once instead of on every call.
+///
+/// Connections are referred to by their index in [Function::routing], which is also the slot their value is cached in while the function runs
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Plan {
+    /// Indexed like [Function::nodes]
+    pub nodes: Vec<NodePlan>,
+    /// Indices of the pure nodes, in order
+    pub pure_nodes: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NodePlan {
+    /// Slots of the inputs of this node: its own [NodeData::input_layout], followed by connected pins it does not declare
+    pub layout: Arc<RegisterLayout>,
+    /// Where the value of each slot comes from, indexed like [NodePlan::layout]
+    pub pins: Vec<PinPlan>,
+    /// Connections out of this node, in the order they fire
+    pub outputs: Vec<Output>,
+    /// Data connections into this node from pure nodes, which are evaluated on demand
+    pub pure_inputs: Vec<PureInput>,
+    /// Connections into this node from volatile nodes, which are cleared before it runs
+    pub volatile_inputs: Vec<usize>,
+    /// Every pure node this node may pull data from, ordered so each comes after all pure nodes it depends on
+    pub dependencies: Vec<usize>,
+    /// A cycle of pure nodes this node depends on. Such a cycle can never be evaluated
+    pub cycle: Option<Vec<usize>>,
+    /// See [crate::nodes::NodeData::is_pure]
+    pub pure: bool,
+    /// Nodes with execution pins whose data this node reads, directly or through its dependencies. Only filled in for pure nodes
+    pub sources: Vec<usize>,
+    /// This node or one of its dependencies is volatile, so its result can change without any of its sources running again
+    pub volatile: bool,
+}
+
+/// Where an input slot of a node gets its value from
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PinPlan {
+    /// Connections into the pin. The last one holding data wins
+    pub connections: Vec<usize>,
+    /// Whether a value has to be found elsewhere when no connection holds data. False for execution pins and undeclared pins
+    pub required: bool,
+    /// Value set on the node instance in the function
+    pub constant: Option<Var>,
+    /// The node can do without a value
+    pub has_default: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Output {
+    pub pin: StringName,
+    pub connection: usize,
+    pub to: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PureInput {
+    pub connection: usize,
+    pub from: usize,
+}
+
+impl Plan {
+    pub fn compile(func: &Function) -> Self {
+        let mut nodes = func
+            .nodes()
+            .iter()
+            .map(|node| {
+                let mut layout = node.node.input_layout();
+                let exec_inputs = node.node.get_exec_inputs();
+                let pins = layout
+                    .names()
+                    .iter()
+                    .map(|pin| PinPlan {
+                        connections: Vec::new(),
+                        required: !exec_inputs.contains(pin),
+                        constant: node.constants.0.get(pin).cloned(),
+                        has_default: node.node.has_default(pin),
+                    })
+                    .collect();
+                let connected = func
+                    .routing()
+                    .iter()
+                    .filter(|route| route.to == node.index)
+                    .map(|route| route.to_param.clone());
+                layout.extend(connected);
+                NodePlan {
+                    layout: Arc::new(layout),
+                    pins,
+                    ..Default::default()
+                }
+            })
+            .collect::<Vec<_>>();
+        let mut direct_sources = vec![Vec::new(); nodes.len()];
+
+        for (index, route) in func.routing().iter().enumerate() {
+            if let Some(from) = nodes.get_mut(route.from) {
+                from.outputs.push(Output {
+                    pin: route.from_param.clone(),
+                    connection: index,
+                    to: route.to,
+                });
+            }
+            let source = func.nodes().get(route.from).map(|source| &source.node);
+            let Some(to) = nodes.get_mut(route.to) else {
+                continue;
+            };
+            if let Some(slot) = to.layout.slot(&route.to_param) {
+                to.pins.resize_with(to.layout.len(), PinPlan::default);
+                to.pins[slot].connections.push(index);
+            }
+            if func.is_exec_connection(route) {
+                continue;
+            }
+            if source.is_some_and(|source| source.is_volatile()) {
+                to.volatile_inputs.push(index);
+            }
+            if source.is_some_and(|source| source.is_pure()) {
+                to.pure_inputs.push(PureInput {
+                    connection: index,
+                    from: route.from,
+                });
+            } else if source.is_some() && !direct_sources[route.to].contains(&route.from) {
+                direct_sources[route.to].push(route.from);
+            }
+        }
+
+        for index in 0..nodes.len() {
+            let mut state = vec![Visit::New; nodes.len()];
+            let mut path = Vec::new();
+            let mut order = Vec::new();
+            let mut cycle = None;
+            for input in nodes[index].pure_inputs.iter() {
+                sort_dependencies(
+                    input.from, &nodes, &mut state, &mut path, &mut order, &mut cycle,
+                );
+            }
+            nodes[index].dependencies = order;
+            nodes[index].cycle = cycle;
+        }
+
+        let pure_nodes = (0..nodes.len())
+            .filter(|index| func.nodes()[*index].node.is_pure())
+            .collect::<Vec<_>>();
+        for index in pure_nodes.iter().copied() {
+            let mut sources = direct_sources[index].clone();
+            for dependency in nodes[index].dependencies.iter() {
+                for source in direct_sources[*dependency].iter() {
+                    if !sources.contains(source) {
+                        sources.push(*source);
+                    }
+                }
+            }
+            let volatile = std::iter::once(&index)
+                .chain(nodes[index].dependencies.iter())
+                .any(|node| func.nodes()[*node].node.is_volatile());
+            nodes[index].pure = true;
+            nodes[index].sources = sources;
+            nodes[index].volatile = volatile;
+        }
+
+        Self { nodes, pure_nodes }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Visit {
+    New,
+    OnPath,
+    Done,
+}
+
+/// Depth first walk up the pure inputs of `node`, adding each node to `order` after everything it depends on
+fn sort_dependencies(
+    node: usize,
+    nodes: &[NodePlan],
+    state: &mut Vec<Visit>,
+    path: &mut Vec<usize>,
+    order: &mut Vec<usize>,
+    cycle: &mut Option<Vec<usize>>,
+) {
+    match state[node] {
+        Visit::Done => return,
+        Visit::OnPath => {
+            if cycle.is_none() {
+                let start = path.iter().position(|n| *n == node).unwrap_or(0);
+                *cycle = Some(path[start..].to_vec());
+            }
+            return;
+        }
+        Visit::New => (),
+    }
+    state[node] = Visit::OnPath;
+    path.push(node);
+    for input in nodes[node].pure_inputs.iter() {
+        sort_dependencies(input.from, nodes, state, path, order, cycle);
+    }
+    path.pop();
+    state[node] = Visit::Done;
+    order.push(node);
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        scripts::{Connection, Function},
+        types::GlobalName,
+        Environment,
+    };
+
+    use super::Plan;
+
+    #[test]
+    fn test_compile() {
+        let env = Environment::new();
+        let func = Function::new(
+            &env.nodes,
+            vec![
+                GlobalName::from_path("std.math.add"),
+                GlobalName::from_path("std.math.subtract"),
+                GlobalName::from_path("std.math.add"),
+                GlobalName::from_path("std.control.if"),
+                GlobalName::from_path("std.print"),
+            ],
+            3,
+            vec![
+                Connection::new(0, 1, "c", "b"),     // 0:c => 1:b
+                Connection::new(0, 2, "c", "b"),     // 0:c => 2:b
+                Connection::new(1, 2, "c", "a"),     // 1:c => 2:a
+                Connection::new(3, 4, "if", "exec"), // 3:if => 4:exec
+                Connection::new(2, 4, "c", "text"),  // 2:c => 4:text
+            ],
+        )
+        .unwrap();
+        let plan = Plan::compile(&func);
+
+        // dependencies come after everything they depend on
+        assert_eq!(plan.nodes[4].dependencies, vec![0, 1, 2]);
+        assert_eq!(plan.nodes[2].dependencies, vec![0, 1]);
+        assert!(plan.nodes[3].dependencies.is_empty());
+        assert_eq!(plan.pure_nodes, vec![0, 1, 2]);
+        // nothing with execution pins feeds the pure nodes, so loops never have to reset them
+        assert!(plan.nodes[2].sources.is_empty());
+        assert!(!plan.nodes[2].volatile);
+
+        // execution connections are never pulled on
+        let print = &plan.nodes[4];
+        assert_eq!(print.pure_inputs.len(), 1);
+        let exec = print.layout.slot(&"exec".into()).unwrap();
+        assert_eq!(print.pins[exec].connections, vec![3]);
+        assert!(!print.pins[exec].required);
+        let text = print.layout.slot(&"text".into()).unwrap();
+        assert_eq!(print.pins[text].connections, vec![4]);
+        assert!(print.pins[text].required);
+        assert_eq!(plan.nodes[3].outputs[0].to, 4);
+    }
+
+    #[test]
+    fn test_compile_cycle() {
+        let env = Environment::new();
+        let func = Function::new(
+            &env.nodes,
+            vec![
+                GlobalName::from_path("std.math.add"),
+                GlobalName::from_path("std.math.add"),
+                GlobalName::from_path("std.print"),
+            ],
+            2,
+            vec![
+                Connection::new(0, 1, "c", "a"),    // 0:c => 1:a
+                Connection::new(1, 0, "c", "a"),    // 1:c => 0:a
+                Connection::new(1, 2, "c", "text"), // 1:c => 2:text
+            ],
+        )
+        .unwrap();
+        let plan = Plan::compile(&func);
+        assert_eq!(plan.nodes[2].cycle, Some(vec![1, 0]));
+        assert_eq!(plan.nodes[0].cycle, Some(vec![1, 0]));
+    }
+}
@@ -1,49 +1,142 @@
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::HashMap,
     fmt::Debug,
-    sync::Arc,
+    sync::{Arc, LazyLock, OnceLock},
 };
 
+use log::Level;
+
 use crate::{
-    nodes::{Node, NodeData, NodeError},
-    types::{GlobalName, NamespacedType, StringName, TypeRegistry, Var, VarRegisters},
+    layout::Layout,
+    nodes::{
+        is_reserved_pin, CallNode, LocalAccess, LocalNode, Node, NodeData, NodeError, NodeMeta,
+        FLOW_BREAK, FLOW_CONTINUE, FLOW_LOOP,
+    },
+    plan::{NodePlan, Plan},
+    structs::StructDef,
+    types::{
+        GlobalName, NamespacedType, PinRegisters, PinType, SlotRegisters, StringName, TypeRegistry,
+        Var, VarRegisters,
+    },
     Environment,
 };
 
+/// Pins of local access nodes and loop signals, interned once instead of on every access
+static VALUE: LazyLock<StringName> = LazyLock::new(|| "value".into());
+static THEN: LazyLock<StringName> = LazyLock::new(|| "then".into());
+static BREAK: LazyLock<StringName> = LazyLock::new(|| FLOW_BREAK.into());
+
 #[derive(Debug, Clone)]
 pub struct Script {
     pub name: GlobalName,
+    /// Scripts which had to be loaded before this one
+    pub imports: Vec<GlobalName>,
     pub funcs: HashMap<StringName, Function>,
+    /// Struct types declared by this script. They are registered on the [Environment] separately
+    pub structs: Vec<StructDef>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Function {
     // TODO: refactor node storage to store each unique node on the script and only store names on the function itself, which should reduce memory usage
-    pub nodes: Vec<FunctionNode>,
+    nodes: Vec<FunctionNode>,
     pub entry: usize,
-    pub routing: Vec<Connection>,
+    routing: Vec<Connection>,
+    /// Presented for [crate::nodes::ScriptNode]s calling this function
+    pub meta: NodeMeta,
+    /// Ids of the nodes from the file this function was read from, or empty if they were referred to by index
+    pub ids: Vec<String>,
+    /// Declared inputs. When empty, the function accepts whatever its caller provides
+    pub inputs: Vec<Param>,
+    /// Declared outputs. When empty, the function returns every value it produced
+    pub outputs: Vec<Param>,
+    /// Variables each call gets its own copy of, starting at their default or the default value of their type
+    pub locals: Vec<Param>,
+    /// Kept only so editors can write the function back the way it was drawn
+    pub layout: Layout,
+    /// Compiled on the first call from the nodes and routing, and dropped whenever either is changed
+    plan: OnceLock<Plan>,
+}
+
+/// A declared input or output of a [Function]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    pub name: StringName,
+    pub ty: PinType,
+    /// Used when the caller provides no value for an input, or the function produces no value for an output
+    pub default: Option<Var>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Connection {
-    /// Cached value for this connection
-    pub value: Var,
     pub from: usize,
     pub to: usize,
     pub from_param: StringName,
     pub to_param: StringName,
 }
 
+/// A connection whose pins carry incompatible types
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeMismatch {
+    /// Index of the connection in [Function::routing]
+    pub connection: usize,
+    pub from: usize,
+    pub from_param: StringName,
+    pub from_type: PinType,
+    pub to: usize,
+    pub to_param: StringName,
+    pub to_type: PinType,
+}
+
 #[derive(Clone, PartialEq)]
 pub struct FunctionNode {
     pub index: usize,
     pub node: Arc<Node>,
+    /// Values of this instance's inputs which have no connection, used before looking in the function inputs
+    pub constants: VarRegisters,
 }
 
 #[derive(Default, Clone, Debug)]
 struct FrameResults {
     blackboard: VarRegisters,
     next_nodes: Vec<usize>,
+    /// The node asked for the fired exec pins to run as a loop body, after which it is executed again
+    looping: bool,
+    signal: ExecSignal,
+}
+
+/// How a run along the execution pins ended
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+enum ExecSignal {
+    #[default]
+    Completed,
+    Break,
+    Continue,
+}
+
+/// Mutable state of a single function call
+struct CallFrame<'a> {
+    script: &'a Script,
+    func_name: StringName,
+    func: &'a Function,
+    plan: &'a Plan,
+    /// Cached value of every connection, indexed like [Function::routing]
+    slots: Vec<Var>,
+    /// Pure nodes which have to be evaluated before the node currently being backfilled, indexed like [Function::nodes]
+    needed: Vec<bool>,
+    env: Arc<Environment>,
+    /// Bound inputs of the call, which unconnected node inputs of the same name read
+    inputs: VarRegisters,
+    /// Local variables of this call, only reachable through [crate::nodes::LocalNode]s
+    locals: VarRegisters,
+    /// Every value produced so far, of which the outputs of the call are collected. Nodes never read from it
+    produced: VarRegisters,
+    /// Indices of every node executed during this call, in order
+    trace: Vec<usize>,
+    /// Execution pins fired by a node without leading anywhere in the function, which are how it reaches its execution outputs
+    exits: Vec<StringName>,
+    /// Number of function calls this call is nested in
+    depth: usize,
 }
 
 impl Connection {
@@ -54,7 +147,6 @@ impl Connection {
         to_param_name: impl Into<StringName>,
     ) -> Self {
         Self {
-            value: Var::Null,
             from,
             to,
             from_param: from_param_name.into(),
@@ -64,24 +156,280 @@ impl Connection {
 }
 
 impl Function {
+    /// Builds a function from registered node types. Fails on the first unknown type, since skipping it would shift the index of every later node
     pub fn new(
         registry: &TypeRegistry<Node>,
         nodes: Vec<GlobalName>,
         entry: usize,
         routing: Vec<Connection>,
-    ) -> Self {
+    ) -> Result<Self, NodeError> {
+        let mut resolved = Vec::with_capacity(nodes.len());
+        for (index, type_name) in nodes.into_iter().enumerate() {
+            let Some(node) = registry.get(&type_name) else {
+                return Err(NodeError::TypeNotFound {
+                    name: type_name,
+                    msg: format!("Unknown node type for node #{index}"),
+                });
+            };
+            resolved.push(node);
+        }
+        Ok(Self::from_nodes(resolved, entry, routing))
+    }
+
+    /// Builds a function from nodes which are already resolved, such as [crate::nodes::CallNode]s which are not registered anywhere
+    pub fn from_nodes(nodes: Vec<Arc<Node>>, entry: usize, routing: Vec<Connection>) -> Self {
         Function {
             nodes: nodes
-                .iter()
-                .filter_map(|type_name| registry.get(type_name))
+                .into_iter()
                 .enumerate()
-                .map(|(index, node)| FunctionNode { index, node })
+                .map(|(index, node)| FunctionNode {
+                    index,
+                    node,
+                    constants: VarRegisters::new(),
+                })
                 .collect(),
-
             entry,
             routing,
+            meta: NodeMeta::default(),
+            ids: Vec::new(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            locals: Vec::new(),
+            layout: Layout::default(),
+            plan: OnceLock::new(),
         }
     }
+
+    /// How this function is executed, compiled from its nodes and routing the first time it is asked for
+    pub fn plan(&self) -> &Plan {
+        self.plan.get_or_init(|| Plan::compile(self))
+    }
+
+    pub fn nodes(&self) -> &[FunctionNode] {
+        &self.nodes
+    }
+
+    /// Changes the nodes, recompiling the plan on the next call
+    pub fn nodes_mut(&mut self) -> &mut Vec<FunctionNode> {
+        self.plan = OnceLock::new();
+        &mut self.nodes
+    }
+
+    pub fn routing(&self) -> &[Connection] {
+        &self.routing
+    }
+
+    /// Changes the connections, recompiling the plan on the next call
+    pub fn routing_mut(&mut self) -> &mut Vec<Connection> {
+        self.plan = OnceLock::new();
+        &mut self.routing
+    }
+
+    /// Whether this function declares any inputs or outputs
+    pub fn has_signature(&self) -> bool {
+        !self.inputs.is_empty() || !self.outputs.is_empty()
+    }
+
+    pub fn input_types(&self) -> PinRegisters {
+        Param::registers(&self.inputs)
+    }
+
+    pub fn output_types(&self) -> PinRegisters {
+        Param::registers(&self.outputs)
+    }
+
+    /// Fills in defaults for missing inputs and checks provided inputs against their declared types
+    fn bind_inputs(
+        &self,
+        name: &GlobalName,
+        mut inputs: VarRegisters,
+    ) -> Result<VarRegisters, NodeError> {
+        for param in self.inputs.iter().filter(|p| p.ty != PinType::Exec) {
+            let value = match inputs.0.get(&param.name) {
+                Some(value) if *value != Var::Null => value.clone(),
+                _ => {
+                    if let Some(default) = &param.default {
+                        inputs.0.insert(param.name.clone(), default.clone());
+                    }
+                    continue;
+                }
+            };
+            if !param.ty.matches(&value) {
+                return Err(NodeError::MismatchedData {
+                    name: name.clone(),
+                    arg: param.name.clone(),
+                    expected: param.ty.default_value(),
+                    received: value,
+                    msg: format!("Expected {} for function input", param.ty),
+                });
+            }
+        }
+        Ok(inputs)
+    }
+
+    /// Starting values of the local variables for a new call
+    fn init_locals(&self) -> VarRegisters {
+        VarRegisters(
+            self.locals
+                .iter()
+                .map(|local| {
+                    let value = local
+                        .default
+                        .clone()
+                        .unwrap_or_else(|| local.ty.default_value());
+                    (local.name.clone(), value)
+                })
+                .collect(),
+        )
+    }
+
+    /// Narrows everything the function produced down to its declared outputs.
+    ///
+    /// Declared execution outputs fire when a node fired an unconnected pin of the same name. A lone execution output always fires once the function completes
+    fn collect_outputs(&self, produced: VarRegisters, exits: &[StringName]) -> VarRegisters {
+        if self.outputs.is_empty() {
+            return produced;
+        }
+        let completed = self
+            .outputs
+            .iter()
+            .filter(|param| param.ty == PinType::Exec)
+            .count()
+            == 1;
+        let mut outputs = VarRegisters::new();
+        for param in self.outputs.iter() {
+            let value = match param.ty {
+                PinType::Exec => Var::Execution(completed || exits.contains(&param.name)),
+                _ => produced
+                    .0
+                    .get(&param.name)
+                    .filter(|value| **value != Var::Null)
+                    .cloned()
+                    .or_else(|| param.default.clone())
+                    .unwrap_or_default(),
+            };
+            outputs.0.insert(param.name.clone(), value);
+        }
+        outputs
+    }
+
+    /// Finds every cycle along data connections. Such cycles can never be evaluated, while cycles along execution connections are just jumps
+    pub fn find_data_cycles(&self) -> Vec<Vec<usize>> {
+        let mut edges = vec![Vec::new(); self.nodes.len()];
+        for route in self.routing.iter() {
+            if route.from < self.nodes.len()
+                && route.to < self.nodes.len()
+                && !self.is_exec_connection(route)
+            {
+                edges[route.from].push(route.to);
+            }
+        }
+
+        // 0 = unvisited, 1 = on the current path, 2 = done
+        let mut state = vec![0u8; self.nodes.len()];
+        let mut path = Vec::new();
+        let mut cycles = Vec::<Vec<usize>>::new();
+        fn visit(
+            node: usize,
+            edges: &Vec<Vec<usize>>,
+            state: &mut Vec<u8>,
+            path: &mut Vec<usize>,
+            cycles: &mut Vec<Vec<usize>>,
+        ) {
+            state[node] = 1;
+            path.push(node);
+            for next in edges[node].iter().cloned() {
+                match state[next] {
+                    0 => visit(next, edges, state, path, cycles),
+                    1 => {
+                        let start = path.iter().position(|n| *n == next).unwrap_or(0);
+                        let cycle = path[start..].to_vec();
+                        if !cycles.contains(&cycle) {
+                            cycles.push(cycle);
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            path.pop();
+            state[node] = 2;
+        }
+        for node in 0..self.nodes.len() {
+            if state[node] == 0 {
+                visit(node, &edges, &mut state, &mut path, &mut cycles);
+            }
+        }
+        cycles
+    }
+
+    /// Checks every connection against the declared pin types of the nodes on both ends, reporting all incompatible connections.
+    ///
+    /// Pins which are not declared by their node are skipped, as there is no type to check against
+    pub fn check_types(&self) -> Vec<TypeMismatch> {
+        let mut mismatches = Vec::new();
+        for (index, route) in self.routing.iter().enumerate() {
+            let (Some(from), Some(to)) = (self.nodes.get(route.from), self.nodes.get(route.to))
+            else {
+                continue;
+            };
+            let Some(from_type) = from
+                .node
+                .get_output_types()
+                .0
+                .get(&route.from_param)
+                .cloned()
+            else {
+                continue;
+            };
+            let Some(to_type) = to.node.get_input_types().0.get(&route.to_param).cloned() else {
+                continue;
+            };
+            if !to_type.accepts(&from_type) {
+                mismatches.push(TypeMismatch {
+                    connection: index,
+                    from: route.from,
+                    from_param: route.from_param.clone(),
+                    from_type,
+                    to: route.to,
+                    to_param: route.to_param.clone(),
+                    to_type,
+                });
+            }
+        }
+        mismatches
+    }
+
+    /// Whether a connection carries execution flow rather than data. Either end being an execution pin is enough
+    pub fn is_exec_connection(&self, route: &Connection) -> bool {
+        let from_exec = self
+            .nodes
+            .get(route.from)
+            .is_some_and(|node| node.node.get_exec_outputs().contains(&route.from_param));
+        let to_exec = self
+            .nodes
+            .get(route.to)
+            .is_some_and(|node| node.node.get_exec_inputs().contains(&route.to_param));
+        from_exec || to_exec
+    }
+}
+
+impl Param {
+    pub fn new(name: impl Into<StringName>, ty: PinType) -> Self {
+        Self {
+            name: name.into(),
+            ty,
+            default: None,
+        }
+    }
+
+    pub fn registers(params: &[Param]) -> PinRegisters {
+        PinRegisters(
+            params
+                .iter()
+                .map(|param| (param.name.clone(), param.ty.clone()))
+                .collect(),
+        )
+    }
 }
 
 impl NamespacedType for Script {
@@ -91,196 +439,449 @@ impl NamespacedType for Script {
 }
 
 impl Script {
+    /// Executes a function of this script.
+    ///
+    /// Execution starts at the entry node and follows the execution pins which each node fires. Data pins are evaluated lazily: before a node runs, any pure nodes feeding its inputs are evaluated first and their results cached for the connection.
     pub fn call_func(
         &self,
         func_name: StringName,
         env: Arc<Environment>,
         inputs: VarRegisters,
-        call_stack: &mut Vec<FunctionNode>,
     ) -> Result<VarRegisters, NodeError> {
-        let Some(func) = &mut self.funcs.get(&func_name).cloned() else {
+        self.call_func_at_depth(func_name, env, inputs, 0)
+    }
+
+    fn call_func_at_depth(
+        &self,
+        func_name: StringName,
+        env: Arc<Environment>,
+        inputs: VarRegisters,
+        depth: usize,
+    ) -> Result<VarRegisters, NodeError> {
+        if depth > env.max_call_depth {
+            return Err(NodeError::StackOverflow {
+                name: GlobalName::from_path(format!(
+                    "{}{}{}",
+                    self.name.to_path(),
+                    CallNode::DELIM,
+                    func_name
+                )),
+                depth,
+            });
+        }
+        let Some(func) = self.funcs.get(&func_name) else {
             return Err(NodeError::TypeNotFound {
                 name: GlobalName::from_path(func_name.to_string()),
                 msg: format!("Function not found on script: {:?}", self.name),
             });
         };
-        env.logger.debug(format!(
-            "calling function {:?} with inputs: \n{:#?}",
-            func_name, inputs
-        ));
-        let mut blackboard = inputs;
-        let Some(entry) = func.nodes.get(func.entry) else {
+        if env.logger.enabled(Level::Debug) {
+            env.logger.debug(format!(
+                "calling function {:?} with inputs: \n{:#?}",
+                func_name, inputs
+            ));
+        }
+        if func.nodes.get(func.entry).is_none() {
             return Err(NodeError::Unhandled(format!("Failed to find entry node for function {:?}::{:?} at index {} of node array with {} elements", self.name, func_name, func.entry, func.nodes.len())));
+        }
+        let entry = func.entry;
+        let inputs = func.bind_inputs(&self.name, inputs)?;
+        let locals = func.init_locals();
+        let plan = func.plan();
+        let mut frame = CallFrame {
+            script: self,
+            func_name,
+            func,
+            plan,
+            slots: vec![Var::Null; func.routing.len()],
+            needed: vec![false; func.nodes.len()],
+            env,
+            produced: inputs.clone(),
+            inputs,
+            locals,
+            trace: Vec::new(),
+            exits: Vec::new(),
+            depth,
         };
-        call_stack.push(entry.clone());
 
-        while !call_stack.is_empty() {
-            // clean call stack
-            Self::purge_duplicate_calls(call_stack, &env);
+        match frame.run_exec(vec![entry])? {
+            ExecSignal::Completed => {
+                // setting a local is how a function returns a value no single node produces
+                let mut produced = frame.produced;
+                produced.0.extend(frame.locals.0);
+                Ok(frame.func.collect_outputs(produced, &frame.exits))
+            }
+            signal => Err(NodeError::Unhandled(format!(
+                "Received {:?} outside of any loop in function {:?}::{:?}",
+                signal, self.name, frame.func_name
+            ))),
+        }
+    }
+}
 
-            // ensure top of stack is present (this should never fail)
-            let Some(top) = call_stack.pop() else {
-                break;
-            };
+impl<'a> CallFrame<'a> {
+    /// Runs nodes along the execution pins, starting with `start`, until no more pins fire or a break/continue is requested
+    fn run_exec(&mut self, start: Vec<usize>) -> Result<ExecSignal, NodeError> {
+        // the stack is LIFO, so push in reverse to run the first connection first
+        let mut exec_stack = start.into_iter().rev().collect::<Vec<_>>();
 
-            // Determine if any inputs need to be loaded first
-            if let Some(exec) = self.get_backfill_nodes(func, &top) {
-                call_stack.push(top); // reset stack
-                for in_node in exec {
-                    let Some(in_node_ref) = func.nodes.get(in_node) else {
-                        return Err(NodeError::Unhandled(format!("Failed to find entry node for function {:?}::{:?} at index {} of node array with {} elements", self.name, func_name, in_node, func.nodes.len())));
-                    };
-                    env.logger
-                        .debug(format!("Backfilling node: {:?}", in_node_ref));
-                    call_stack.push(in_node_ref.clone());
-                }
-                continue;
+        while let Some(index) = exec_stack.pop() {
+            let node = self.get_node(index)?;
+            let mut results = self.run_node(node, VarRegisters::new())?;
+            if results.signal != ExecSignal::Completed {
+                return Ok(results.signal);
             }
 
-            // generate valid input registers
-            let inputs = self.get_input_register(func, &top, &blackboard)?;
+            while results.looping {
+                let body_start = self.trace.len();
+                let signal = self.run_exec(results.next_nodes)?;
+                self.reset_loop_body(index, body_start);
 
-            // execute the current node
-            let results = self.execute_frame(func, &func_name, &env, inputs, &top)?;
-            env.logger
-                .debug(format!("{:?} Outputs: \n{:#?}", top, results));
+                // loop nodes are stateless, so hand them back what they produced last iteration
+                let mut state = results.blackboard;
+                if signal == ExecSignal::Break {
+                    state.0.insert(BREAK.clone(), Var::Execution(true));
+                }
+                results = self.run_node(node, state)?;
+            }
 
-            // merge blackboards
-            for entry in results.blackboard.0 {
-                blackboard.0.insert(entry.0, entry.1);
+            for index in results.next_nodes.into_iter().rev() {
+                if self.env.logger.enabled(Level::Debug) {
+                    self.env.logger.debug(format!("Pushing node: #{}", index));
+                }
+                exec_stack.push(index);
             }
+        }
+        Ok(ExecSignal::Completed)
+    }
+
+    /// Loads the inputs of a node, executes it and records its outputs
+    fn run_node(
+        &mut self,
+        node: &FunctionNode,
+        extra_inputs: VarRegisters,
+    ) -> Result<FrameResults, NodeError> {
+        // make sure all data feeding this node is available
+        self.backfill(node)?;
+
+        // generate valid input registers
+        let inputs = self.get_input_register(node)?;
 
-            env.logger
-                .debug(format!("Current blackboard: {:?}", blackboard));
+        // execute the current node
+        let results = self.execute_frame(inputs, extra_inputs, node)?;
+        if self.env.logger.enabled(Level::Debug) {
+            self.env
+                .logger
+                .debug(format!("{:?} Outputs: \n{:#?}", node, results));
+        }
 
-            for index in results.next_nodes {
-                let Some(node) = func.nodes.get(func.entry) else {
-                    return Err(NodeError::Unhandled(format!("Failed to find next node for function {:?}::{:?} at index {} of node array with {} elements", self.name, func_name, index, func.nodes.len())));
-                };
-                env.logger.debug(format!("Pushing node: {:?}", node));
-                call_stack.push(node.clone());
+        for entry in results.blackboard.0.iter() {
+            if !is_reserved_pin(entry.0.as_str()) {
+                self.produced.0.insert(entry.0.clone(), entry.1.clone());
             }
+        }
+        if self.env.logger.enabled(Level::Debug) {
+            self.env
+                .logger
+                .debug(format!("Current locals: {:?}", self.locals));
+        }
 
-            // debug frame connection data
-            env.logger
-                .debug(format!("Frame connection data \n{:#?}", func.routing));
+        Ok(results)
+    }
+
+    /// Clears the cached data of every node with execution pins a loop body executed, and of the pure nodes reading from them or from the loop node, so the next iteration evaluates them again.
+    ///
+    /// Pure nodes which only read data the loop leaves alone keep their results across iterations
+    fn reset_loop_body(&mut self, loop_node: usize, body_start: usize) {
+        let plans = self.plan;
+        let body = &self.trace[body_start..];
+        for index in plans.pure_nodes.iter() {
+            let plan = &plans.nodes[*index];
+            let stale = plan.volatile
+                || plan
+                    .sources
+                    .iter()
+                    .any(|source| *source == loop_node || body.contains(source));
+            if stale {
+                for output in plan.outputs.iter() {
+                    self.slots[output.connection] = Var::Null;
+                }
+            }
         }
+        for index in body.iter().filter(|index| !plans.nodes[**index].pure) {
+            for output in plans.nodes[*index].outputs.iter() {
+                self.slots[output.connection] = Var::Null;
+            }
+        }
+    }
 
-        Ok(blackboard.clone())
+    fn get_node(&self, index: usize) -> Result<&'a FunctionNode, NodeError> {
+        let func = self.func;
+        func.nodes.get(index).ok_or_else(|| {
+            NodeError::Unhandled(format!(
+                "Failed to find node for function {:?}::{:?} at index {} of node array with {} elements",
+                self.script.name,
+                self.func_name,
+                index,
+                func.nodes.len()
+            ))
+        })
     }
 
-    fn purge_duplicate_calls(call_stack: &mut Vec<FunctionNode>, env: &Arc<Environment>) {
-        let mut stack_indices = Vec::<usize>::new();
-        let mut n_stack = VecDeque::<FunctionNode>::new();
-        for entry in call_stack.iter().rev() {
-            if stack_indices.contains(&entry.index) {
-                env.logger
-                    .debug(format!("Dropping duplicate call in stack to {:?}", entry));
+    /// Evaluates every pure node which feeds an empty data connection into `node`, along with the pure nodes those depend on.
+    ///
+    /// The first pass walks the dependencies from `node` upwards to find which are needed, the second evaluates those in order
+    fn backfill(&mut self, node: &FunctionNode) -> Result<(), NodeError> {
+        let plans = self.plan;
+        let plan = &plans.nodes[node.index];
+        self.mark_needed(plan);
+        for dependency in plan.dependencies.iter().rev().cloned() {
+            if !self.needed[dependency] {
+                continue;
+            }
+            if let Some(cycle) = plan
+                .cycle
+                .as_ref()
+                .filter(|cycle| cycle.contains(&dependency))
+            {
+                return Err(NodeError::Unhandled(format!(
+                    "Data cycle detected in function {:?}::{:?} while evaluating {:?}. Nodes on the cycle: {:?}",
+                    self.script.name, self.func_name, node, cycle
+                )));
+            }
+            self.mark_needed(&plans.nodes[dependency]);
+        }
+
+        for dependency in plan.dependencies.iter().cloned() {
+            if !self.needed[dependency] {
                 continue;
             }
-            stack_indices.push(entry.index);
-            n_stack.push_front(entry.clone());
+            self.needed[dependency] = false;
+            let source = self.get_node(dependency)?;
+            if self.env.logger.enabled(Level::Debug) {
+                self.env
+                    .logger
+                    .debug(format!("Backfilling node: {:?}", source));
+            }
+            self.check_pure_inputs(&plans.nodes[dependency])?;
+            let inputs = self.get_input_register(source)?;
+            let results = self.execute_frame(inputs, VarRegisters::new(), source)?;
+            for entry in results.blackboard.0 {
+                self.produced.0.insert(entry.0, entry.1);
+            }
         }
-        call_stack.clear();
-        for e in n_stack {
-            call_stack.push(e);
+        self.check_pure_inputs(plan)
+    }
+
+    /// Clears the volatile inputs of a node about to be evaluated, then marks the pure nodes feeding its empty connections as needed
+    fn mark_needed(&mut self, plan: &NodePlan) {
+        for connection in plan.volatile_inputs.iter() {
+            self.slots[*connection] = Var::Null;
+        }
+        for input in plan.pure_inputs.iter() {
+            if self.slots[input.connection] == Var::Null {
+                self.needed[input.from] = true;
+            }
         }
     }
 
-    fn get_input_register(
-        &self,
-        func: &Function,
-        node: &FunctionNode,
-        blackboard: &VarRegisters,
-    ) -> Result<VarRegisters, NodeError> {
-        let target = node.index;
-        let inputs = func
-            .routing
+    /// Fails if a pure node was evaluated but left one of the connections into this node empty
+    fn check_pure_inputs(&self, plan: &NodePlan) -> Result<(), NodeError> {
+        let Some(input) = plan
+            .pure_inputs
             .iter()
-            .filter_map(|route| {
-                if route.to != target {
-                    return None;
-                }
-                if route.value == Var::Null {
-                    return None;
+            .find(|input| self.slots[input.connection] == Var::Null)
+        else {
+            return Ok(());
+        };
+        Err(NodeError::NullException {
+            name: self.get_node(input.from)?.node.get_name(),
+            arg: self.func.routing[input.connection].from_param.clone(),
+            msg: "Node did not produce a value for a connected output".into(),
+        })
+    }
+
+    /// Fills the input slots of a node from its connections, falling back to its constants, then the function inputs and finally the node's own defaults
+    fn get_input_register(&self, node: &FunctionNode) -> Result<SlotRegisters, NodeError> {
+        let plan = &self.plan.nodes[node.index];
+        let mut registers = SlotRegisters::new(plan.layout.clone());
+        for (slot, pin) in plan.pins.iter().enumerate() {
+            for connection in pin.connections.iter() {
+                let value = &self.slots[*connection];
+                if *value != Var::Null && !value.is_execution() {
+                    registers.set(slot, value.clone());
                 }
-                Some((route.to_param.clone(), route.value.clone()))
-            })
-            .collect::<Vec<_>>();
-        let mut registers = VarRegisters::new();
-        for (key, value) in inputs {
-            registers.0.insert(key, value);
-        }
-        for req in node.node.get_inputs() {
-            if registers.0.contains_key(&req) {
+            }
+            if !pin.required || *registers.get(slot) != Var::Null {
                 continue;
             }
-            if let Some(entry) = blackboard.0.get(&req) {
-                registers.0.insert(req, entry.clone());
+            if let Some(value) = &pin.constant {
+                registers.set(slot, value.clone());
+                continue;
+            }
+            let Some(name) = plan.layout.name(slot) else {
+                continue;
+            };
+            if let Some(entry) = self.inputs.0.get(name) {
+                registers.set(slot, entry.clone());
+                continue;
+            }
+            if pin.has_default {
                 continue;
             }
             return Err(NodeError::NullException {
                 name: node.node.get_name(),
-                arg: req.clone(),
-                msg: "Failed to find valid input between blackboard and connections".into(),
+                arg: name.clone(),
+                msg: "Failed to find valid input between connections and function inputs".into(),
             });
         }
         Ok(registers)
     }
 
-    /// Determines if any nodes need to be injected into the call stack first to load the registers
-    fn get_backfill_nodes(&self, func: &Function, node: &FunctionNode) -> Option<Vec<usize>> {
-        let target = node.index;
-        let invalid_inputs = func
-            .routing
-            .iter()
-            .filter(|p| p.to == target && p.value == Var::Null)
-            .collect::<Vec<_>>();
-        if invalid_inputs.is_empty() {
-            return None;
+    /// Accesses a local variable of this call
+    fn access_local(
+        &mut self,
+        local: &LocalNode,
+        inputs: &SlotRegisters,
+    ) -> Result<VarRegisters, NodeError> {
+        let mut outputs = VarRegisters::new();
+        match local.access {
+            LocalAccess::Get => {
+                let value = self
+                    .locals
+                    .0
+                    .get(&local.name)
+                    .cloned()
+                    .unwrap_or_else(|| local.ty.default_value());
+                outputs.0.insert(VALUE.clone(), value);
+            }
+            LocalAccess::Set => {
+                let value = inputs.get_named(&VALUE).clone();
+                if value != Var::Null && !local.ty.matches(&value) {
+                    return Err(NodeError::MismatchedData {
+                        name: local.get_name(),
+                        arg: VALUE.clone(),
+                        expected: local.ty.default_value(),
+                        received: value,
+                        msg: format!("Expected {} for local {:?}", local.ty, local.name),
+                    });
+                }
+                self.locals.0.insert(local.name.clone(), value.clone());
+                outputs.0.insert(THEN.clone(), Var::Execution(true));
+                outputs.0.insert(VALUE.clone(), value);
+            }
         }
-        Some(invalid_inputs.iter().map(|route| route.from).collect())
+        Ok(outputs)
     }
 
+    /// Runs a call node one level deeper than this frame, resolving calls without a script to this frame's script
+    fn call(&self, call: &CallNode, inputs: VarRegisters) -> Result<VarRegisters, NodeError> {
+        let Some(name) = &call.script else {
+            return self.script.call_func_at_depth(
+                call.func.clone(),
+                self.env.clone(),
+                inputs,
+                self.depth + 1,
+            );
+        };
+        let Some(script) = self.env.scripts.get(name) else {
+            return Err(NodeError::TypeNotFound {
+                name: name.clone(),
+                msg: format!("No script registered for call to {:?}", call.to_path()),
+            });
+        };
+        script.call_func_at_depth(call.func.clone(), self.env.clone(), inputs, self.depth + 1)
+    }
+
+    /// Executes a node, reading its inputs by slot unless it is handed `extra_inputs`, which only loop nodes get
     fn execute_frame(
-        &self,
-        func: &mut Function,
-        _func_name: &StringName,
-        env: &Arc<Environment>,
-        inputs: VarRegisters,
+        &mut self,
+        inputs: SlotRegisters,
+        extra_inputs: VarRegisters,
         node: &FunctionNode,
     ) -> Result<FrameResults, NodeError> {
-        env.logger
-            .debug(format!("Executing {:?} with inputs: \n{:#?}", node, inputs));
-        let next_frame = node.node.execute(env.clone(), inputs)?;
-
-        let mut outputs = func
-            .routing
-            .iter_mut()
-            .filter(|p| p.from == node.index)
-            .collect::<Vec<_>>();
+        if self.env.logger.enabled(Level::Debug) {
+            self.env
+                .logger
+                .debug(format!("Executing {:?} with inputs: \n{:#?}", node, inputs));
+        }
+        let next_frame = match node.node.as_ref() {
+            Node::Local(local) => self.access_local(local, &inputs)?,
+            Node::Basic(basic) if extra_inputs.0.is_empty() => {
+                basic.execute_slots(self.env.clone(), &inputs)?
+            }
+            other => {
+                let mut named = inputs.to_registers();
+                named.0.extend(extra_inputs.0);
+                match other {
+                    Node::Call(call) => self.call(call, named)?,
+                    Node::Script(script) => script.script.call_func_at_depth(
+                        script.func.clone(),
+                        self.env.clone(),
+                        named,
+                        self.depth + 1,
+                    )?,
+                    other => other.execute(self.env.clone(), named)?,
+                }
+            }
+        };
+        self.trace.push(node.index);
 
         let mut results = FrameResults::default();
 
-        for (key, var) in &next_frame.0 {
-            for out in outputs.iter_mut() {
-                if let Var::Execution(enabled) = var {
-                    if *enabled {
-                        results.next_nodes.push(out.to);
+        let plans = self.plan;
+        for output in plans.nodes[node.index].outputs.iter() {
+            let Some(var) = next_frame.0.get(&output.pin) else {
+                continue;
+            };
+            if *var == Var::Execution(true) {
+                results.next_nodes.push(output.to);
+            }
+            self.slots[output.connection] = var.clone();
+        }
+
+        for (key, var) in next_frame.0 {
+            if var == Var::Execution(true) {
+                match key.as_str() {
+                    FLOW_LOOP => results.looping = true,
+                    FLOW_BREAK => results.signal = ExecSignal::Break,
+                    FLOW_CONTINUE => results.signal = ExecSignal::Continue,
+                    _ if !plans.nodes[node.index]
+                        .outputs
+                        .iter()
+                        .any(|output| output.pin == key) =>
+                    {
+                        self.exits.push(key.clone())
                     }
+                    _ => (),
                 }
-                if out.from_param == key.clone() {
-                    out.value = var.clone();
-                    continue;
-                }
             }
-            results.blackboard.0.insert(key.clone(), var.clone());
+            // execution pulses only matter to the routing, not to the data
+            if !var.is_execution() {
+                results.blackboard.0.insert(key, var);
+            }
         }
 
         Ok(results)
     }
 }
 
+impl std::fmt::Display for TypeMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "connection #{}: output #{}.{} ({}) cannot feed input #{}.{} ({})",
+            self.connection,
+            self.from,
+            self.from_param,
+            self.from_type,
+            self.to,
+            self.to_param,
+            self.to_type
+        )
+    }
+}
+
 impl Debug for FunctionNode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_fmt(format_args!("{:?} #{}", self.node.get_name(), self.index))
@@ -289,14 +890,21 @@ impl Debug for FunctionNode {
 
 #[cfg(test)]
 mod test {
-    use std::{collections::HashMap, sync::Arc};
+    use std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
 
     use crate::{
-        types::{GlobalName, Var, VarRegisters},
+        nodes::{BasicNode, BasicNodeLogic, Node, NodeMeta},
+        types::{GlobalName, PinRegisters, PinType, Var, VarRegisters},
         Environment,
     };
 
-    use super::{Connection, Function, Script};
+    use super::{Connection, Function, FunctionNode, Param, Script};
 
     struct TestScript {
         script: Script,
@@ -307,7 +915,9 @@ mod test {
         let env = Environment::new();
         let mut script = Script {
             name: GlobalName::from_path("test.script"),
+            imports: Vec::new(),
             funcs: HashMap::new(),
+            structs: Vec::new(),
         };
         script.funcs.insert(
             "func".into(),
@@ -324,7 +934,8 @@ mod test {
                     Connection::new(0, 2, "c", "b"), // 0:c => 2:b
                     Connection::new(1, 2, "c", "a"), // 1:c => 2:a
                 ],
-            ),
+            )
+            .unwrap(),
         );
         TestScript { script, env }
     }
@@ -357,7 +968,6 @@ mod test {
                 ("a".into(), Var::Num(3.0)),
                 ("b".into(), Var::Num(4.0)),
             ])),
-            &mut Vec::new(),
         );
 
         eprintln!("{:#?}", result);
@@ -379,7 +989,6 @@ mod test {
                     ("a".into(), Var::Num(3.0)),
                     ("b".into(), Var::Num(4.0)),
                 ])),
-                &mut Vec::new(),
             );
             eprintln!("Calling func iter: {i}");
             assert!(result.is_ok());
@@ -387,4 +996,339 @@ mod test {
             assert_eq!(output.0.get(&"c".into()).cloned(), Some(Var::Num(3.0)));
         }
     }
+
+    fn get_branch_script(env: &Environment) -> Script {
+        let mut script = Script {
+            name: GlobalName::from_path("test.branch"),
+            imports: Vec::new(),
+            funcs: HashMap::new(),
+            structs: Vec::new(),
+        };
+        script.funcs.insert(
+            "func".into(),
+            Function::new(
+                &env.nodes,
+                vec![
+                    GlobalName::from_path("std.control.if"),
+                    GlobalName::from_path("std.math.add"),
+                    GlobalName::from_path("std.math.subtract"),
+                ],
+                0,
+                vec![
+                    Connection::new(0, 1, "if", "exec"),   // 0:if => 1:exec
+                    Connection::new(0, 2, "else", "exec"), // 0:else => 2:exec
+                ],
+            )
+            .unwrap(),
+        );
+        script
+    }
+
+    #[test]
+    /// Validates that execution follows only the pins fired by `std.control.if`
+    ///
+    /// Script simulates:
+    /// ```
+    /// let c = if flag { a + b } else { a - b };
+    /// ```
+    fn test_script_branching() {
+        let env = Arc::new(Environment::new());
+        let script = get_branch_script(&env);
+        for (flag, expected) in [(true, 7.0), (false, -1.0)] {
+            let result = script.call_func(
+                "func".into(),
+                env.clone(),
+                VarRegisters(HashMap::from([
+                    ("a".into(), Var::Num(3.0)),
+                    ("b".into(), Var::Num(4.0)),
+                    ("flag".into(), Var::Bool(flag)),
+                ])),
+            );
+            let output = result.unwrap();
+            assert_eq!(output.0.get(&"c".into()).cloned(), Some(Var::Num(expected)));
+        }
+    }
+
+    #[test]
+    /// Changing a function after it was called recompiles its plan
+    fn test_script_edit_after_call() {
+        let env = Arc::new(Environment::new());
+        let add = GlobalName::from_path("std.math.add");
+        let mut script = get_branch_script(&env);
+        script.funcs.insert(
+            "func".into(),
+            Function::new(&env.nodes, vec![add.clone()], 0, Vec::new()).unwrap(),
+        );
+        let inputs = VarRegisters(HashMap::from([
+            ("a".into(), Var::Num(1.0)),
+            ("b".into(), Var::Num(2.0)),
+        ]));
+        let output = script
+            .call_func("func".into(), env.clone(), inputs.clone())
+            .unwrap();
+        assert_eq!(output.0.get(&"c".into()), Some(&Var::Num(3.0)));
+
+        // (1 + 10) + 2
+        let func = script.funcs.get_mut(&"func".into()).unwrap();
+        func.nodes_mut().push(FunctionNode {
+            index: 1,
+            node: env.nodes.get(&add).unwrap(),
+            constants: VarRegisters(HashMap::from([("b".into(), Var::Num(10.0))])),
+        });
+        func.routing_mut().push(Connection::new(1, 0, "c", "a"));
+        let output = script.call_func("func".into(), env, inputs).unwrap();
+        assert_eq!(output.0.get(&"c".into()), Some(&Var::Num(13.0)));
+    }
+
+    #[test]
+    /// Only the execution outputs a function reached fire for its caller
+    fn test_script_exec_outputs() {
+        let env = Arc::new(Environment::new());
+        let mut func = Function::new(
+            &env.nodes,
+            vec![GlobalName::from_path("std.control.if")],
+            0,
+            Vec::new(),
+        )
+        .unwrap();
+        func.outputs = vec![
+            Param::new("if", PinType::Exec),
+            Param::new("else", PinType::Exec),
+        ];
+        let mut script = get_branch_script(&env);
+        script.funcs.insert("func".into(), func);
+        for flag in [true, false] {
+            let output = script
+                .call_func(
+                    "func".into(),
+                    env.clone(),
+                    VarRegisters(HashMap::from([("flag".into(), Var::Bool(flag))])),
+                )
+                .unwrap();
+            assert_eq!(
+                output.0.get(&"if".into()).cloned(),
+                Some(Var::Execution(flag))
+            );
+            assert_eq!(
+                output.0.get(&"else".into()).cloned(),
+                Some(Var::Execution(!flag))
+            );
+        }
+
+        // a function with a single execution output fires it by completing
+        let mut func = Function::new(
+            &env.nodes,
+            vec![GlobalName::from_path("std.math.add")],
+            0,
+            Vec::new(),
+        )
+        .unwrap();
+        func.outputs = vec![Param::new("then", PinType::Exec)];
+        script.funcs.insert("func".into(), func);
+        let output = script
+            .call_func(
+                "func".into(),
+                env.clone(),
+                VarRegisters(HashMap::from([
+                    ("a".into(), Var::Num(1.0)),
+                    ("b".into(), Var::Num(2.0)),
+                ])),
+            )
+            .unwrap();
+        assert_eq!(
+            output.0.get(&"then".into()).cloned(),
+            Some(Var::Execution(true))
+        );
+    }
+
+    #[test]
+    /// A data cycle can never be satisfied, so it must be reported instead of looping forever
+    fn test_script_data_cycle() {
+        let env = Environment::new();
+        let mut script = Script {
+            name: GlobalName::from_path("test.cycle"),
+            imports: Vec::new(),
+            funcs: HashMap::new(),
+            structs: Vec::new(),
+        };
+        script.funcs.insert(
+            "func".into(),
+            Function::new(
+                &env.nodes,
+                vec![
+                    GlobalName::from_path("std.math.add"),
+                    GlobalName::from_path("std.math.add"),
+                ],
+                0,
+                vec![
+                    Connection::new(0, 1, "c", "a"), // 0:c => 1:a
+                    Connection::new(1, 0, "c", "a"), // 1:c => 0:a
+                ],
+            )
+            .unwrap(),
+        );
+        let result = script.call_func(
+            "func".into(),
+            Arc::new(env),
+            VarRegisters(HashMap::from([("b".into(), Var::Num(1.0))])),
+        );
+        assert!(result.is_err());
+    }
+
+    /// Builds a function which runs `body` once per index of `for_range(0, 5)`, then `std.vars.value` once completed
+    ///
+    /// Node 0 is the loop, node 1 the completion marker, and body nodes start at index 2
+    fn get_loop_script(env: &Environment, body: Vec<&str>, routing: Vec<Connection>) -> Script {
+        let mut nodes = vec![
+            GlobalName::from_path("std.control.for_range"),
+            GlobalName::from_path("std.vars.value"),
+        ];
+        nodes.extend(body.into_iter().map(GlobalName::from_path));
+        let mut connections = vec![Connection::new(0, 1, "completed", "exec")];
+        connections.extend(routing);
+        let mut script = Script {
+            name: GlobalName::from_path("test.loop"),
+            imports: Vec::new(),
+            funcs: HashMap::new(),
+            structs: Vec::new(),
+        };
+        script.funcs.insert(
+            "func".into(),
+            Function::new(&env.nodes, nodes, 0, connections).unwrap(),
+        );
+        script
+    }
+
+    fn run_loop_script(env: Environment, script: Script) -> VarRegisters {
+        let output = script
+            .call_func(
+                "func".into(),
+                Arc::new(env),
+                VarRegisters(HashMap::from([
+                    ("start".into(), Var::Num(0.0)),
+                    ("end".into(), Var::Num(5.0)),
+                    ("b".into(), Var::Num(10.0)),
+                    ("value".into(), Var::String("Bool(true)".into())),
+                ])),
+            )
+            .unwrap();
+        // the completed pin must always fire once the loop is over
+        assert_eq!(output.0.get(&"var".into()).cloned(), Some(Var::Bool(true)));
+        output
+    }
+
+    #[test]
+    /// Pure nodes inside a loop body must be evaluated again on every iteration
+    ///
+    /// Script simulates:
+    /// ```
+    /// for index in 0..5 {
+    ///     c = (index + b) - b;
+    /// }
+    /// ```
+    fn test_script_loop() {
+        let env = Environment::new();
+        let script = get_loop_script(
+            &env,
+            vec!["std.math.add", "std.math.subtract"],
+            vec![
+                Connection::new(0, 3, "body", "exec"), // 0:body => 3:exec
+                Connection::new(0, 2, "index", "a"),   // 0:index => 2:a
+                Connection::new(2, 3, "c", "a"),       // 2:c => 3:a
+            ],
+        );
+        let output = run_loop_script(env, script);
+        assert_eq!(output.0.get(&"c".into()).cloned(), Some(Var::Num(4.0)));
+    }
+
+    #[test]
+    /// Pure nodes which read nothing a loop changes are evaluated once, not on every iteration
+    fn test_script_loop_invariant() {
+        let evaluated = Arc::new(AtomicUsize::new(0));
+        let counter = evaluated.clone();
+        let mut env = Environment::new();
+        env.nodes.register(Node::Basic(BasicNode {
+            name: GlobalName::from_path("test.count"),
+            inputs: PinRegisters::new(),
+            outputs: PinRegisters(HashMap::from([("c".into(), PinType::Num)])),
+            logic: BasicNodeLogic::new(move |_, _| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(VarRegisters(HashMap::from([("c".into(), Var::Num(1.0))])))
+            }),
+            meta: NodeMeta::default(),
+            volatile: false,
+        }));
+        let script = get_loop_script(
+            &env,
+            vec!["test.count", "std.math.add"],
+            vec![
+                Connection::new(0, 3, "body", "exec"), // 0:body => 3:exec
+                Connection::new(0, 3, "index", "a"),   // 0:index => 3:a
+                Connection::new(2, 3, "c", "b"),       // 2:c => 3:b
+            ],
+        );
+        let output = run_loop_script(env, script);
+        assert_eq!(output.0.get(&"c".into()).cloned(), Some(Var::Num(5.0)));
+        assert_eq!(evaluated.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_script_loop_break() {
+        let env = Environment::new();
+        let script = get_loop_script(
+            &env,
+            vec!["std.control.break"],
+            vec![Connection::new(0, 2, "body", "exec")],
+        );
+        let output = run_loop_script(env, script);
+        assert_eq!(output.0.get(&"index".into()).cloned(), Some(Var::Num(0.0)));
+    }
+
+    #[test]
+    fn test_script_loop_continue() {
+        let env = Environment::new();
+        let script = get_loop_script(
+            &env,
+            vec!["std.control.continue", "std.math.add"],
+            vec![
+                Connection::new(0, 2, "body", "exec"), // 0:body => 2:exec
+                Connection::new(0, 3, "body", "exec"), // 0:body => 3:exec, skipped by the continue
+                Connection::new(0, 3, "index", "a"),   // 0:index => 3:a
+            ],
+        );
+        let output = run_loop_script(env, script);
+        assert_eq!(output.0.get(&"index".into()).cloned(), Some(Var::Num(4.0)));
+        assert_eq!(output.0.get(&"c".into()), None);
+    }
+
+    #[test]
+    fn test_check_types() {
+        let env = Environment::new();
+        let func = Function::new(
+            &env.nodes,
+            vec![
+                GlobalName::from_path("std.list.len"),
+                GlobalName::from_path("std.control.if"),
+                GlobalName::from_path("std.math.add"),
+                GlobalName::from_path("std.print"),
+            ],
+            1,
+            vec![
+                Connection::new(0, 1, "len", "flag"), // Int => Bool
+                Connection::new(0, 2, "len", "a"),    // Int => Num
+                Connection::new(1, 3, "if", "exec"),  // Exec => Exec
+                Connection::new(2, 3, "c", "exec"),   // Num => Exec
+            ],
+        )
+        .unwrap();
+        let mismatches = func.check_types();
+        assert_eq!(
+            mismatches.iter().map(|m| m.connection).collect::<Vec<_>>(),
+            vec![0, 3]
+        );
+        assert_eq!(mismatches[0].from_type, PinType::Int);
+        assert_eq!(mismatches[0].to_type, PinType::Bool);
+        assert_eq!(mismatches[1].from_type, PinType::Num);
+        assert_eq!(mismatches[1].to_type, PinType::Exec);
+    }
 }
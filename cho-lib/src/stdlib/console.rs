@@ -1,8 +1,8 @@
 use std::{collections::HashMap, sync::Arc};
 
 use crate::{
-    nodes::{BasicNodeLogic, Node, NodeError},
-    types::{GlobalName, StringName, TypeRegistry, Var, VarRegisters},
+    nodes::{BasicNodeLogic, Node, NodeError, NodeMeta},
+    types::{GlobalName, PinType, StringName, TypeRegistry, Var, VarRegisters},
     Environment,
 };
 
@@ -12,14 +12,20 @@ pub fn register(registry: &mut TypeRegistry<Node>) {
     add_basic(
         registry,
         GlobalName::from_path("std.print"),
-        vec![("exec", Var::Execution(false))],
-        vec![],
+        vec![("exec", PinType::Exec), ("text", PinType::String)],
+        vec![("then", PinType::Exec)],
         BasicNodeLogic::new(node_std_print),
+        NodeMeta::new("Logs text to the console")
+            .with_category("console")
+            .with_tags(["log", "output", "debug"])
+            .with_pin("text", "Text to log"),
     );
 }
 
 fn node_std_print(env: Arc<Environment>, inputs: VarRegisters) -> Result<VarRegisters, NodeError> {
     let text = get_var_string(&GlobalName::from_path("std.print"), &inputs, "text".into())?;
-    env.logger.info(format!("{text}"));
-    Ok(VarRegisters::default())
+    env.logger.info(text);
+    let mut out = VarRegisters::new();
+    out.0.insert("then".into(), Var::Execution(true));
+    Ok(out)
 }
@@ -1,25 +1,158 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    ops::Bound,
+    sync::{Arc, LazyLock},
+};
 
 use crate::{
-    nodes::{BasicNodeLogic, Node, NodeError},
-    types::{GlobalName, TypeRegistry, Var, VarRegisters},
+    nodes::{
+        BasicNodeLogic, Node, NodeError, NodeMeta, FLOW_BREAK, FLOW_CONTINUE, FLOW_CURSOR,
+        FLOW_LOOP,
+    },
+    types::{GlobalName, PinType, StringName, TypeRegistry, Var, VarRegisters},
     Environment,
 };
 
-use super::{add_basic, get_var_bool, get_var_number};
+use super::{add_basic, get_var, get_var_bool, get_var_number};
+
+const STD_IF: &str = "std.control.if";
+const STD_FOR_RANGE: &str = "std.control.for_range";
+const STD_WHILE: &str = "std.control.while";
+const STD_FOR_EACH: &str = "std.control.for_each";
+const STD_BREAK: &str = "std.control.break";
+const STD_CONTINUE: &str = "std.control.continue";
 
-const STD_IF: &'static str = "std.control.if";
+/// Names used on every execution, and every iteration of a loop, interned once instead of per call
+static STD_IF_NAME: LazyLock<GlobalName> = LazyLock::new(|| GlobalName::from_path(STD_IF));
+static STD_FOR_RANGE_NAME: LazyLock<GlobalName> =
+    LazyLock::new(|| GlobalName::from_path(STD_FOR_RANGE));
+static STD_WHILE_NAME: LazyLock<GlobalName> = LazyLock::new(|| GlobalName::from_path(STD_WHILE));
+static STD_FOR_EACH_NAME: LazyLock<GlobalName> =
+    LazyLock::new(|| GlobalName::from_path(STD_FOR_EACH));
+static FLAG: LazyLock<StringName> = LazyLock::new(|| "flag".into());
+static IF: LazyLock<StringName> = LazyLock::new(|| "if".into());
+static ELSE: LazyLock<StringName> = LazyLock::new(|| "else".into());
+static START: LazyLock<StringName> = LazyLock::new(|| "start".into());
+static END: LazyLock<StringName> = LazyLock::new(|| "end".into());
+static CONDITION: LazyLock<StringName> = LazyLock::new(|| "condition".into());
+static ITEMS: LazyLock<StringName> = LazyLock::new(|| "items".into());
+static INDEX: LazyLock<StringName> = LazyLock::new(|| "index".into());
+static ITEM: LazyLock<StringName> = LazyLock::new(|| "item".into());
+static BODY: LazyLock<StringName> = LazyLock::new(|| "body".into());
+static COMPLETED: LazyLock<StringName> = LazyLock::new(|| "completed".into());
+static LOOP: LazyLock<StringName> = LazyLock::new(|| FLOW_LOOP.into());
+static BREAK: LazyLock<StringName> = LazyLock::new(|| FLOW_BREAK.into());
+static CONTINUE: LazyLock<StringName> = LazyLock::new(|| FLOW_CONTINUE.into());
+static CURSOR: LazyLock<StringName> = LazyLock::new(|| FLOW_CURSOR.into());
 
 pub fn register(registry: &mut TypeRegistry<Node>) {
     add_basic(
         registry,
         GlobalName::from_path(STD_IF),
-        vec![("flag", Var::Bool(false))],
+        vec![("exec", PinType::Exec), ("flag", PinType::Bool)],
+        vec![("if", PinType::Exec), ("else", PinType::Exec)],
+        BasicNodeLogic::new(node_std_if_else),
+        NodeMeta::new("Continues through `if` or `else` depending on a flag")
+            .with_display_name("Branch")
+            .with_category("control")
+            .with_tags(["branch", "condition"]),
+    );
+    add_basic(
+        registry,
+        GlobalName::from_path(STD_FOR_RANGE),
         vec![
-            ("if", Var::Execution(false)),
-            ("else", Var::Execution(false)),
+            ("exec", PinType::Exec),
+            ("start", PinType::Int),
+            ("end", PinType::Int),
         ],
-        BasicNodeLogic::new(node_std_if_else),
+        vec![
+            ("body", PinType::Exec),
+            ("index", PinType::Int),
+            ("completed", PinType::Exec),
+        ],
+        BasicNodeLogic::new(node_std_for_range),
+        NodeMeta::new("Runs `body` once for every index from `start` up to but excluding `end`")
+            .with_display_name("For Range")
+            .with_category("control")
+            .with_tags(["loop", "for", "range"])
+            .with_pin_default("start", "First index", Var::Int(0))
+            .with_pin("end", "Index to stop before")
+            .with_pin("index", "Index of the current iteration")
+            .with_pin(
+                "completed",
+                "Fires once the loop has finished or was broken",
+            ),
+    );
+    add_basic(
+        registry,
+        GlobalName::from_path(STD_WHILE),
+        vec![("exec", PinType::Exec), ("condition", PinType::Bool)],
+        vec![("body", PinType::Exec), ("completed", PinType::Exec)],
+        BasicNodeLogic::new(node_std_while),
+        NodeMeta::new("Runs `body` for as long as `condition` is true")
+            .with_display_name("While")
+            .with_category("control")
+            .with_tags(["loop"])
+            .with_pin("condition", "Evaluated again before every iteration")
+            .with_pin(
+                "completed",
+                "Fires once the loop has finished or was broken",
+            ),
+    );
+    add_basic(
+        registry,
+        GlobalName::from_path(STD_FOR_EACH),
+        vec![("exec", PinType::Exec), ("items", PinType::Any)],
+        vec![
+            ("body", PinType::Exec),
+            ("item", PinType::Any),
+            ("index", PinType::Int),
+            ("completed", PinType::Exec),
+        ],
+        BasicNodeLogic::new(node_std_for_each),
+        NodeMeta::new(
+            "Runs `body` once for every item of a list, key of a map or character of a string",
+        )
+        .with_display_name("For Each")
+        .with_category("control")
+        .with_tags(["loop", "for", "iterate"])
+        .with_pin("items", "List, map or string to iterate")
+        .with_pin("item", "Item of the current iteration")
+        .with_pin("index", "Position of the current item")
+        .with_pin(
+            "completed",
+            "Fires once the loop has finished or was broken",
+        ),
+    );
+    add_basic(
+        registry,
+        GlobalName::from_path(STD_BREAK),
+        vec![("exec", PinType::Exec)],
+        vec![],
+        BasicNodeLogic::new(|_, _| {
+            Ok(VarRegisters(HashMap::from([(
+                BREAK.clone(),
+                Var::Execution(true),
+            )])))
+        }),
+        NodeMeta::new("Stops the innermost loop")
+            .with_category("control")
+            .with_tags(["loop", "exit"]),
+    );
+    add_basic(
+        registry,
+        GlobalName::from_path(STD_CONTINUE),
+        vec![("exec", PinType::Exec)],
+        vec![],
+        BasicNodeLogic::new(|_, _| {
+            Ok(VarRegisters(HashMap::from([(
+                CONTINUE.clone(),
+                Var::Execution(true),
+            )])))
+        }),
+        NodeMeta::new("Skips the rest of the current iteration of the innermost loop")
+            .with_category("control")
+            .with_tags(["loop", "skip", "next"]),
     );
 }
 
@@ -27,14 +160,154 @@ fn node_std_if_else(
     _env: Arc<Environment>,
     inputs: VarRegisters,
 ) -> Result<VarRegisters, NodeError> {
-    let name = GlobalName::from_path(STD_IF);
-    let flag = get_var_bool(&name, &inputs, "flag".into())?;
+    let flag = get_var_bool(&STD_IF_NAME, &inputs, FLAG.clone())?;
     Ok(VarRegisters(HashMap::from([
-        ("if".into(), Var::Execution(flag)),
-        ("else".into(), Var::Execution(!flag)),
+        (IF.clone(), Var::Execution(flag)),
+        (ELSE.clone(), Var::Execution(!flag)),
     ])))
 }
 
+/// Output registers for a loop which still has iterations left
+fn loop_iteration(state: impl IntoIterator<Item = (&'static StringName, Var)>) -> VarRegisters {
+    let mut out = VarRegisters::new();
+    for (key, value) in state {
+        out.0.insert(key.clone(), value);
+    }
+    out.0.insert(BODY.clone(), Var::Execution(true));
+    out.0.insert(COMPLETED.clone(), Var::Execution(false));
+    out.0.insert(LOOP.clone(), Var::Execution(true));
+    out
+}
+
+/// Output registers for a loop which has finished
+fn loop_completed() -> VarRegisters {
+    VarRegisters(HashMap::from([
+        (BODY.clone(), Var::Execution(false)),
+        (COMPLETED.clone(), Var::Execution(true)),
+    ]))
+}
+
+fn loop_broken(inputs: &VarRegisters) -> bool {
+    inputs.0.get(&BREAK) == Some(&Var::Execution(true))
+}
+
+/// Index of the next iteration, based on the index the loop node emitted for the previous one
+fn next_index(name: &GlobalName, inputs: &VarRegisters, first: Var) -> Result<Var, NodeError> {
+    match inputs.0.get(&INDEX) {
+        None => Ok(first),
+        Some(Var::Int(index)) => {
+            index
+                .checked_add(1)
+                .map(Var::Int)
+                .ok_or_else(|| NodeError::Overflow {
+                    name: name.clone(),
+                    msg: "Loop index overflowed".into(),
+                })
+        }
+        Some(_) => Ok(Var::Num(get_var_number(name, inputs, INDEX.clone())? + 1.0)),
+    }
+}
+
+fn node_std_for_range(
+    _env: Arc<Environment>,
+    inputs: VarRegisters,
+) -> Result<VarRegisters, NodeError> {
+    let name = &*STD_FOR_RANGE_NAME;
+    if loop_broken(&inputs) {
+        return Ok(loop_completed());
+    }
+    // integer bounds produce integer indices, any float bound makes them floats. An empty start is the default of its pin
+    let start = match inputs.0.get(&START) {
+        None | Some(Var::Null) => Var::Int(0),
+        Some(Var::Int(start)) => Var::Int(*start),
+        Some(_) => Var::Num(get_var_number(name, &inputs, START.clone())?),
+    };
+    let index = next_index(name, &inputs, start)?;
+    let finished = match (&index, get_var(name, &inputs, END.clone())?) {
+        (Var::Int(index), Var::Int(end)) => *index >= end,
+        _ => index.as_number() >= Some(get_var_number(name, &inputs, END.clone())?),
+    };
+    if finished {
+        return Ok(loop_completed());
+    }
+    Ok(loop_iteration([(&*INDEX, index)]))
+}
+
+fn node_std_while(_env: Arc<Environment>, inputs: VarRegisters) -> Result<VarRegisters, NodeError> {
+    if loop_broken(&inputs) || !get_var_bool(&STD_WHILE_NAME, &inputs, CONDITION.clone())? {
+        return Ok(loop_completed());
+    }
+    Ok(loop_iteration([]))
+}
+
+fn node_std_for_each(
+    _env: Arc<Environment>,
+    inputs: VarRegisters,
+) -> Result<VarRegisters, NodeError> {
+    let name = &*STD_FOR_EACH_NAME;
+    if loop_broken(&inputs) {
+        return Ok(loop_completed());
+    }
+    let index = next_index(name, &inputs, Var::Int(0))?;
+    let cursor = inputs.0.get(&CURSOR);
+    // lists yield their items, maps their keys and strings their characters. The cursor is where the previous iteration left off, so each one only looks at the next item
+    let next = match inputs.0.get(&ITEMS).unwrap_or(&Var::Null) {
+        Var::List(list) => {
+            let position = cursor.and_then(Var::as_int).unwrap_or(0);
+            usize::try_from(position)
+                .ok()
+                .and_then(|position| list.get(position))
+                .map(|item| (item.clone(), Var::Int(position + 1)))
+        }
+        Var::Map(map) => {
+            let after = match cursor {
+                Some(Var::String(last)) => Bound::Excluded(last.as_str()),
+                _ => Bound::Unbounded,
+            };
+            map.range::<str, _>((after, Bound::Unbounded))
+                .next()
+                .map(|(key, _)| (Var::String(key.clone()), Var::String(key.clone())))
+        }
+        Var::String(text) => {
+            let offset = cursor.and_then(Var::as_int).unwrap_or(0);
+            usize::try_from(offset)
+                .ok()
+                .and_then(|offset| text.get(offset..))
+                .and_then(|rest| rest.chars().next())
+                .map(|c| {
+                    (
+                        Var::String(c.into()),
+                        Var::Int(offset + c.len_utf8() as i64),
+                    )
+                })
+        }
+        Var::Null => {
+            return Err(NodeError::NullException {
+                name: name.clone(),
+                arg: ITEMS.clone(),
+                msg: "Field was found null".into(),
+            })
+        }
+        received => {
+            return Err(NodeError::MismatchedData {
+                name: name.clone(),
+                arg: ITEMS.clone(),
+                expected: Var::List(vec![]),
+                received: received.clone(),
+                msg: "Can only iterate over lists, maps and strings".into(),
+            })
+        }
+    };
+    let Some((item, cursor)) = next else {
+        return Ok(loop_completed());
+    };
+    Ok(loop_iteration([
+        (&*ITEM, item),
+        (&*INDEX, index),
+        (&*CURSOR, cursor),
+    ]))
+}
+
 #[cfg(test)]
 mod test {
     use std::{collections::HashMap, sync::Arc};
@@ -45,12 +318,30 @@ mod test {
         Environment,
     };
 
-    use super::node_std_if_else;
+    use super::{node_std_for_each, node_std_for_range, node_std_if_else, FLOW_BREAK, FLOW_LOOP};
+
+    type LoopLogic = fn(Arc<Environment>, VarRegisters) -> Result<VarRegisters, NodeError>;
 
     fn get_env() -> Arc<Environment> {
         Arc::new(Environment::new_empty())
     }
 
+    /// Runs a loop node to completion the way a function does, handing back its outputs every iteration
+    fn collect_loop(logic: LoopLogic, inputs: VarRegisters, pin: &'static str) -> Vec<Var> {
+        let env = get_env();
+        let mut state = inputs.clone();
+        let mut values = Vec::new();
+        loop {
+            let reg = logic(env.clone(), state).unwrap();
+            if reg.0.get(&"completed".into()) == Some(&Var::Execution(true)) {
+                return values;
+            }
+            values.push(reg.0.get(&pin.into()).cloned().unwrap());
+            state = inputs.clone();
+            state.0.extend(reg.0);
+        }
+    }
+
     #[test]
     fn test_if_else() {
         let env = get_env();
@@ -84,4 +375,94 @@ mod test {
             Some(Var::Execution(true))
         );
     }
+
+    #[test]
+    fn test_for_range() {
+        let env = get_env();
+        let mut inputs = VarRegisters(HashMap::from([
+            ("start".into(), Var::Num(1.0)),
+            ("end".into(), Var::Num(3.0)),
+        ]));
+        let mut indices = Vec::new();
+        loop {
+            let reg = node_std_for_range(env.clone(), inputs.clone()).unwrap();
+            if reg.0.get(&"completed".into()) == Some(&Var::Execution(true)) {
+                break;
+            }
+            assert_eq!(reg.0.get(&FLOW_LOOP.into()), Some(&Var::Execution(true)));
+            let index = reg.0.get(&"index".into()).cloned().unwrap();
+            indices.push(index.clone());
+            inputs.0.insert("index".into(), index);
+        }
+        assert_eq!(indices, vec![Var::Num(1.0), Var::Num(2.0)]);
+
+        // a break request always completes the loop
+        inputs.0.remove(&"index".into());
+        inputs.0.insert(FLOW_BREAK.into(), Var::Execution(true));
+        let reg = node_std_for_range(env.clone(), inputs).unwrap();
+        assert_eq!(reg.0.get(&"completed".into()), Some(&Var::Execution(true)));
+    }
+
+    #[test]
+    fn test_for_range_int() {
+        let inputs = VarRegisters(HashMap::from([
+            ("start".into(), Var::Int(i64::MAX - 1)),
+            ("end".into(), Var::Int(i64::MAX)),
+        ]));
+        let reg = node_std_for_range(get_env(), inputs.clone()).unwrap();
+        assert_eq!(
+            reg.0.get(&"index".into()).cloned(),
+            Some(Var::Int(i64::MAX - 1))
+        );
+        let mut next = inputs;
+        next.0.insert("index".into(), Var::Int(i64::MAX - 1));
+        let reg = node_std_for_range(get_env(), next).unwrap();
+        assert_eq!(reg.0.get(&"completed".into()), Some(&Var::Execution(true)));
+    }
+
+    #[test]
+    fn test_for_range_default_start() {
+        let inputs = VarRegisters(HashMap::from([("end".into(), Var::Int(3))]));
+        assert_eq!(
+            collect_loop(node_std_for_range, inputs, "index"),
+            vec![Var::Int(0), Var::Int(1), Var::Int(2)]
+        );
+    }
+
+    #[test]
+    fn test_for_each() {
+        let items = |items: Var| VarRegisters(HashMap::from([("items".into(), items)]));
+        let list = Var::List(vec![Var::Bool(true), Var::Int(2)]);
+        assert_eq!(
+            collect_loop(node_std_for_each, items(list.clone()), "item"),
+            vec![Var::Bool(true), Var::Int(2)]
+        );
+        assert_eq!(
+            collect_loop(node_std_for_each, items(list), "index"),
+            vec![Var::Int(0), Var::Int(1)]
+        );
+
+        let map = Var::Map(
+            [("b", Var::Null), ("a", Var::Null)]
+                .map(|(key, value)| (key.to_string(), value))
+                .into(),
+        );
+        assert_eq!(
+            collect_loop(node_std_for_each, items(map), "item"),
+            vec![Var::String("a".into()), Var::String("b".into())]
+        );
+
+        // characters wider than a byte are still yielded whole
+        let text = Var::String("añb".into());
+        assert_eq!(
+            collect_loop(node_std_for_each, items(text), "item"),
+            vec![
+                Var::String("a".into()),
+                Var::String("ñ".into()),
+                Var::String("b".into())
+            ]
+        );
+
+        assert!(node_std_for_each(get_env(), items(Var::Int(1))).is_err());
+    }
 }
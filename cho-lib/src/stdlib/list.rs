@@ -0,0 +1,297 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    nodes::{BasicNodeLogic, Node, NodeError, NodeMeta},
+    types::{GlobalName, PinType, TypeRegistry, Var, VarRegisters},
+    Environment,
+};
+
+use super::{add_basic, get_var, get_var_index, get_var_list};
+
+const STD_LIST_PUSH: &str = "std.list.push";
+const STD_LIST_GET: &str = "std.list.get";
+const STD_LIST_LEN: &str = "std.list.len";
+const STD_LIST_SLICE: &str = "std.list.slice";
+const STD_LIST_CONTAINS: &str = "std.list.contains";
+const STD_LIST_REMOVE: &str = "std.list.remove";
+
+pub fn register(registry: &mut TypeRegistry<Node>) {
+    add_basic(
+        registry,
+        GlobalName::from_path(STD_LIST_PUSH),
+        vec![
+            ("list", PinType::list_of(PinType::Any)),
+            ("item", PinType::Any),
+        ],
+        vec![("list", PinType::list_of(PinType::Any))],
+        BasicNodeLogic::new(node_std_list_push),
+        NodeMeta::new("Copies a list with an item added to its end")
+            .with_category("list")
+            .with_tags(["append", "add"]),
+    );
+    add_basic(
+        registry,
+        GlobalName::from_path(STD_LIST_GET),
+        vec![
+            ("list", PinType::list_of(PinType::Any)),
+            ("index", PinType::Int),
+        ],
+        vec![("item", PinType::Any)],
+        BasicNodeLogic::new(node_std_list_get),
+        NodeMeta::new("Reads the item at an index. Indices past the end are an error")
+            .with_category("list")
+            .with_tags(["index", "at"]),
+    );
+    add_basic(
+        registry,
+        GlobalName::from_path(STD_LIST_LEN),
+        vec![("list", PinType::list_of(PinType::Any))],
+        vec![("len", PinType::Int)],
+        BasicNodeLogic::new(node_std_list_len),
+        NodeMeta::new("Counts the items of a list")
+            .with_category("list")
+            .with_tags(["length", "count", "size"]),
+    );
+    add_basic(
+        registry,
+        GlobalName::from_path(STD_LIST_SLICE),
+        vec![
+            ("list", PinType::list_of(PinType::Any)),
+            ("start", PinType::Int),
+            ("end", PinType::Int),
+        ],
+        vec![("list", PinType::list_of(PinType::Any))],
+        BasicNodeLogic::new(node_std_list_slice),
+        NodeMeta::new("Copies the items from `start` up to but excluding `end`")
+            .with_category("list")
+            .with_tags(["sublist", "range"]),
+    );
+    add_basic(
+        registry,
+        GlobalName::from_path(STD_LIST_CONTAINS),
+        vec![
+            ("list", PinType::list_of(PinType::Any)),
+            ("item", PinType::Any),
+        ],
+        vec![("contains", PinType::Bool)],
+        BasicNodeLogic::new(node_std_list_contains),
+        NodeMeta::new("Checks whether a list holds an item")
+            .with_category("list")
+            .with_tags(["has", "find"]),
+    );
+    add_basic(
+        registry,
+        GlobalName::from_path(STD_LIST_REMOVE),
+        vec![
+            ("list", PinType::list_of(PinType::Any)),
+            ("index", PinType::Int),
+        ],
+        vec![
+            ("list", PinType::list_of(PinType::Any)),
+            ("item", PinType::Any),
+        ],
+        BasicNodeLogic::new(node_std_list_remove),
+        NodeMeta::new("Copies a list without the item at an index, also outputting that item")
+            .with_category("list")
+            .with_tags(["delete", "take"]),
+    );
+}
+
+fn out_of_range(name: &GlobalName, index: usize, len: usize) -> NodeError {
+    NodeError::Unhandled(format!(
+        "{:?}: index {} is out of range for a list of length {}",
+        name, index, len
+    ))
+}
+
+fn node_std_list_push(
+    _env: Arc<Environment>,
+    inputs: VarRegisters,
+) -> Result<VarRegisters, NodeError> {
+    let name = GlobalName::from_path(STD_LIST_PUSH);
+    let mut list = get_var_list(&name, &inputs, "list".into())?;
+    list.push(get_var(&name, &inputs, "item")?);
+    Ok(VarRegisters(HashMap::from([(
+        "list".into(),
+        Var::List(list),
+    )])))
+}
+
+fn node_std_list_get(
+    _env: Arc<Environment>,
+    inputs: VarRegisters,
+) -> Result<VarRegisters, NodeError> {
+    let name = GlobalName::from_path(STD_LIST_GET);
+    let list = get_var_list(&name, &inputs, "list".into())?;
+    let index = get_var_index(&name, &inputs, "index".into())?;
+    let Some(item) = list.get(index).cloned() else {
+        return Err(out_of_range(&name, index, list.len()));
+    };
+    Ok(VarRegisters(HashMap::from([("item".into(), item)])))
+}
+
+fn node_std_list_len(
+    _env: Arc<Environment>,
+    inputs: VarRegisters,
+) -> Result<VarRegisters, NodeError> {
+    let name = GlobalName::from_path(STD_LIST_LEN);
+    let list = get_var_list(&name, &inputs, "list".into())?;
+    Ok(VarRegisters(HashMap::from([(
+        "len".into(),
+        Var::Int(list.len() as i64),
+    )])))
+}
+
+fn node_std_list_slice(
+    _env: Arc<Environment>,
+    inputs: VarRegisters,
+) -> Result<VarRegisters, NodeError> {
+    let name = GlobalName::from_path(STD_LIST_SLICE);
+    let list = get_var_list(&name, &inputs, "list".into())?;
+    let start = get_var_index(&name, &inputs, "start".into())?;
+    let end = get_var_index(&name, &inputs, "end".into())?;
+    if end > list.len() {
+        return Err(out_of_range(&name, end, list.len()));
+    }
+    if start > end {
+        return Err(NodeError::Unhandled(format!(
+            "{:?}: slice start {} is past its end {}",
+            name, start, end
+        )));
+    }
+    Ok(VarRegisters(HashMap::from([(
+        "list".into(),
+        Var::List(list[start..end].to_vec()),
+    )])))
+}
+
+fn node_std_list_contains(
+    _env: Arc<Environment>,
+    inputs: VarRegisters,
+) -> Result<VarRegisters, NodeError> {
+    let name = GlobalName::from_path(STD_LIST_CONTAINS);
+    let list = get_var_list(&name, &inputs, "list".into())?;
+    let item = get_var(&name, &inputs, "item")?;
+    Ok(VarRegisters(HashMap::from([(
+        "contains".into(),
+        Var::Bool(list.contains(&item)),
+    )])))
+}
+
+fn node_std_list_remove(
+    _env: Arc<Environment>,
+    inputs: VarRegisters,
+) -> Result<VarRegisters, NodeError> {
+    let name = GlobalName::from_path(STD_LIST_REMOVE);
+    let mut list = get_var_list(&name, &inputs, "list".into())?;
+    let index = get_var_index(&name, &inputs, "index".into())?;
+    if index >= list.len() {
+        return Err(out_of_range(&name, index, list.len()));
+    }
+    let item = list.remove(index);
+    Ok(VarRegisters(HashMap::from([
+        ("list".into(), Var::List(list)),
+        ("item".into(), item),
+    ])))
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, sync::Arc};
+
+    use crate::{
+        types::{Var, VarRegisters},
+        Environment,
+    };
+
+    use super::{
+        node_std_list_contains, node_std_list_get, node_std_list_push, node_std_list_remove,
+        node_std_list_slice,
+    };
+
+    fn get_env() -> Arc<Environment> {
+        Arc::new(Environment::new_empty())
+    }
+
+    fn get_list() -> Var {
+        Var::List(vec![Var::Num(1.0), Var::Num(2.0), Var::Num(3.0)])
+    }
+
+    #[test]
+    fn test_push_get() {
+        let res = node_std_list_push(
+            get_env(),
+            VarRegisters(HashMap::from([
+                ("list".into(), get_list()),
+                ("item".into(), Var::String("four".into())),
+            ])),
+        )
+        .unwrap();
+        let list = res.0.get(&"list".into()).cloned().unwrap();
+
+        let res = node_std_list_get(
+            get_env(),
+            VarRegisters(HashMap::from([
+                ("list".into(), list.clone()),
+                ("index".into(), Var::Int(3)),
+            ])),
+        )
+        .unwrap();
+        assert_eq!(
+            res.0.get(&"item".into()).cloned(),
+            Some(Var::String("four".into()))
+        );
+
+        // reading past the end is an error rather than a null
+        let res = node_std_list_get(
+            get_env(),
+            VarRegisters(HashMap::from([
+                ("list".into(), list),
+                ("index".into(), Var::Int(4)),
+            ])),
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_slice_contains_remove() {
+        let res = node_std_list_slice(
+            get_env(),
+            VarRegisters(HashMap::from([
+                ("list".into(), get_list()),
+                ("start".into(), Var::Num(1.0)),
+                ("end".into(), Var::Num(3.0)),
+            ])),
+        )
+        .unwrap();
+        let slice = res.0.get(&"list".into()).cloned().unwrap();
+        assert_eq!(slice, Var::List(vec![Var::Num(2.0), Var::Num(3.0)]));
+
+        let res = node_std_list_contains(
+            get_env(),
+            VarRegisters(HashMap::from([
+                ("list".into(), slice),
+                ("item".into(), Var::Num(1.0)),
+            ])),
+        )
+        .unwrap();
+        assert_eq!(
+            res.0.get(&"contains".into()).cloned(),
+            Some(Var::Bool(false))
+        );
+
+        let res = node_std_list_remove(
+            get_env(),
+            VarRegisters(HashMap::from([
+                ("list".into(), get_list()),
+                ("index".into(), Var::Num(0.0)),
+            ])),
+        )
+        .unwrap();
+        assert_eq!(res.0.get(&"item".into()).cloned(), Some(Var::Num(1.0)));
+        assert_eq!(
+            res.0.get(&"list".into()).cloned(),
+            Some(Var::List(vec![Var::Num(2.0), Var::Num(3.0)]))
+        );
+    }
+}
@@ -0,0 +1,262 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    nodes::{BasicNodeLogic, Node, NodeError, NodeMeta},
+    types::{GlobalName, PinType, TypeRegistry, Var, VarRegisters},
+    Environment,
+};
+
+use super::{add_basic, get_var, get_var_map, get_var_string};
+
+const STD_MAP_INSERT: &str = "std.map.insert";
+const STD_MAP_GET: &str = "std.map.get";
+const STD_MAP_LEN: &str = "std.map.len";
+const STD_MAP_KEYS: &str = "std.map.keys";
+const STD_MAP_CONTAINS: &str = "std.map.contains";
+const STD_MAP_REMOVE: &str = "std.map.remove";
+
+pub fn register(registry: &mut TypeRegistry<Node>) {
+    add_basic(
+        registry,
+        GlobalName::from_path(STD_MAP_INSERT),
+        vec![
+            ("map", PinType::map_of(PinType::Any)),
+            ("key", PinType::String),
+            ("value", PinType::Any),
+        ],
+        vec![("map", PinType::map_of(PinType::Any))],
+        BasicNodeLogic::new(node_std_map_insert),
+        NodeMeta::new("Copies a map with a key set to a value, replacing any previous value")
+            .with_category("map")
+            .with_tags(["set", "put"]),
+    );
+    add_basic(
+        registry,
+        GlobalName::from_path(STD_MAP_GET),
+        vec![
+            ("map", PinType::map_of(PinType::Any)),
+            ("key", PinType::String),
+        ],
+        vec![("value", PinType::Any)],
+        BasicNodeLogic::new(node_std_map_get),
+        NodeMeta::new("Reads the value of a key. Missing keys are an error")
+            .with_category("map")
+            .with_tags(["lookup"]),
+    );
+    add_basic(
+        registry,
+        GlobalName::from_path(STD_MAP_LEN),
+        vec![("map", PinType::map_of(PinType::Any))],
+        vec![("len", PinType::Int)],
+        BasicNodeLogic::new(node_std_map_len),
+        NodeMeta::new("Counts the entries of a map")
+            .with_category("map")
+            .with_tags(["length", "count", "size"]),
+    );
+    add_basic(
+        registry,
+        GlobalName::from_path(STD_MAP_KEYS),
+        vec![("map", PinType::map_of(PinType::Any))],
+        vec![("keys", PinType::list_of(PinType::String))],
+        BasicNodeLogic::new(node_std_map_keys),
+        NodeMeta::new("Lists the keys of a map in sorted order").with_category("map"),
+    );
+    add_basic(
+        registry,
+        GlobalName::from_path(STD_MAP_CONTAINS),
+        vec![
+            ("map", PinType::map_of(PinType::Any)),
+            ("key", PinType::String),
+        ],
+        vec![("contains", PinType::Bool)],
+        BasicNodeLogic::new(node_std_map_contains),
+        NodeMeta::new("Checks whether a map has a key")
+            .with_category("map")
+            .with_tags(["has"]),
+    );
+    add_basic(
+        registry,
+        GlobalName::from_path(STD_MAP_REMOVE),
+        vec![
+            ("map", PinType::map_of(PinType::Any)),
+            ("key", PinType::String),
+        ],
+        vec![
+            ("map", PinType::map_of(PinType::Any)),
+            ("value", PinType::Any),
+        ],
+        BasicNodeLogic::new(node_std_map_remove),
+        NodeMeta::new(
+            "Copies a map without a key, also outputting its value. Missing keys are an error",
+        )
+        .with_category("map")
+        .with_tags(["delete", "take"]),
+    );
+}
+
+fn missing_key(name: &GlobalName, key: String) -> NodeError {
+    NodeError::Unhandled(format!("{:?}: key {:?} is not in the map", name, key))
+}
+
+fn node_std_map_insert(
+    _env: Arc<Environment>,
+    inputs: VarRegisters,
+) -> Result<VarRegisters, NodeError> {
+    let name = GlobalName::from_path(STD_MAP_INSERT);
+    let mut map = get_var_map(&name, &inputs, "map".into())?;
+    let key = get_var_string(&name, &inputs, "key".into())?;
+    map.insert(key, get_var(&name, &inputs, "value")?);
+    Ok(VarRegisters(HashMap::from([("map".into(), Var::Map(map))])))
+}
+
+fn node_std_map_get(
+    _env: Arc<Environment>,
+    inputs: VarRegisters,
+) -> Result<VarRegisters, NodeError> {
+    let name = GlobalName::from_path(STD_MAP_GET);
+    let map = get_var_map(&name, &inputs, "map".into())?;
+    let key = get_var_string(&name, &inputs, "key".into())?;
+    let Some(value) = map.get(&key).cloned() else {
+        return Err(missing_key(&name, key));
+    };
+    Ok(VarRegisters(HashMap::from([("value".into(), value)])))
+}
+
+fn node_std_map_len(
+    _env: Arc<Environment>,
+    inputs: VarRegisters,
+) -> Result<VarRegisters, NodeError> {
+    let name = GlobalName::from_path(STD_MAP_LEN);
+    let map = get_var_map(&name, &inputs, "map".into())?;
+    Ok(VarRegisters(HashMap::from([(
+        "len".into(),
+        Var::Int(map.len() as i64),
+    )])))
+}
+
+fn node_std_map_keys(
+    _env: Arc<Environment>,
+    inputs: VarRegisters,
+) -> Result<VarRegisters, NodeError> {
+    let name = GlobalName::from_path(STD_MAP_KEYS);
+    let map = get_var_map(&name, &inputs, "map".into())?;
+    Ok(VarRegisters(HashMap::from([(
+        "keys".into(),
+        Var::List(map.into_keys().map(Var::String).collect()),
+    )])))
+}
+
+fn node_std_map_contains(
+    _env: Arc<Environment>,
+    inputs: VarRegisters,
+) -> Result<VarRegisters, NodeError> {
+    let name = GlobalName::from_path(STD_MAP_CONTAINS);
+    let map = get_var_map(&name, &inputs, "map".into())?;
+    let key = get_var_string(&name, &inputs, "key".into())?;
+    Ok(VarRegisters(HashMap::from([(
+        "contains".into(),
+        Var::Bool(map.contains_key(&key)),
+    )])))
+}
+
+fn node_std_map_remove(
+    _env: Arc<Environment>,
+    inputs: VarRegisters,
+) -> Result<VarRegisters, NodeError> {
+    let name = GlobalName::from_path(STD_MAP_REMOVE);
+    let mut map = get_var_map(&name, &inputs, "map".into())?;
+    let key = get_var_string(&name, &inputs, "key".into())?;
+    let Some(value) = map.remove(&key) else {
+        return Err(missing_key(&name, key));
+    };
+    Ok(VarRegisters(HashMap::from([
+        ("map".into(), Var::Map(map)),
+        ("value".into(), value),
+    ])))
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::{BTreeMap, HashMap},
+        sync::Arc,
+    };
+
+    use crate::{
+        nodes::NodeData,
+        types::{GlobalName, PinType, Var, VarRegisters},
+        Environment,
+    };
+
+    use super::{node_std_map_get, node_std_map_insert, node_std_map_keys, node_std_map_remove};
+
+    fn get_env() -> Arc<Environment> {
+        Arc::new(Environment::new_empty())
+    }
+
+    fn get_map() -> Var {
+        Var::Map(BTreeMap::from([
+            ("b".into(), Var::Num(2.0)),
+            ("a".into(), Var::Num(1.0)),
+        ]))
+    }
+
+    #[test]
+    fn test_insert_get() {
+        let res = node_std_map_insert(
+            get_env(),
+            VarRegisters(HashMap::from([
+                ("map".into(), get_map()),
+                ("key".into(), Var::String("c".into())),
+                ("value".into(), Var::Bool(true)),
+            ])),
+        )
+        .unwrap();
+        let map = res.0.get(&"map".into()).cloned().unwrap();
+
+        let res = node_std_map_get(
+            get_env(),
+            VarRegisters(HashMap::from([
+                ("map".into(), map),
+                ("key".into(), Var::String("c".into())),
+            ])),
+        )
+        .unwrap();
+        assert_eq!(res.0.get(&"value".into()).cloned(), Some(Var::Bool(true)));
+    }
+
+    #[test]
+    fn test_keys_remove() {
+        let res = node_std_map_keys(
+            get_env(),
+            VarRegisters(HashMap::from([("map".into(), get_map())])),
+        )
+        .unwrap();
+        // keys are always sorted
+        assert_eq!(
+            res.0.get(&"keys".into()).cloned(),
+            Some(Var::List(vec![
+                Var::String("a".into()),
+                Var::String("b".into())
+            ]))
+        );
+        let env = Environment::new();
+        let keys = env
+            .nodes
+            .get(&GlobalName::from_path(super::STD_MAP_KEYS))
+            .unwrap();
+        assert_eq!(
+            keys.get_output_types().0.get(&"keys".into()),
+            Some(&PinType::list_of(PinType::String))
+        );
+
+        let res = node_std_map_remove(
+            get_env(),
+            VarRegisters(HashMap::from([
+                ("map".into(), get_map()),
+                ("key".into(), Var::String("z".into())),
+            ])),
+        );
+        assert!(res.is_err());
+    }
+}
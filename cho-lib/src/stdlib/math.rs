@@ -1,92 +1,148 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock},
+};
 
 use crate::{
-    nodes::{BasicNodeLogic, Node, NodeError},
-    types::{GlobalName, StringName, TypeRegistry, Var, VarRegisters},
+    nodes::{BasicNodeLogic, Node, NodeError, NodeMeta},
+    types::{GlobalName, PinType, SlotRegisters, StringName, TypeRegistry, Var, VarRegisters},
     Environment,
 };
 
-use super::{add_basic, get_var_number};
+use super::{add_basic, get_slot, get_slot_number};
+
+/// Slots of the operands, whose pins are laid out by name
+const A: usize = 0;
+const B: usize = 1;
+
+/// Names used on every execution, interned once instead of per call
+static STD_MATH_ADD: LazyLock<GlobalName> = LazyLock::new(|| GlobalName::from_path("std.math.add"));
+static STD_MATH_SUBTRACT: LazyLock<GlobalName> =
+    LazyLock::new(|| GlobalName::from_path("std.math.subtract"));
+static STD_MATH_MULTIPLY: LazyLock<GlobalName> =
+    LazyLock::new(|| GlobalName::from_path("std.math.multiply"));
+static STD_MATH_DIVIDE: LazyLock<GlobalName> =
+    LazyLock::new(|| GlobalName::from_path("std.math.divide"));
+static C: LazyLock<StringName> = LazyLock::new(|| "c".into());
 
 pub fn register(registry: &mut TypeRegistry<Node>) {
     add_basic(
         registry,
-        GlobalName::from_path("std.math.add"),
-        vec![("a", Var::Num(0.0)), ("b", Var::Num(0.0))],
-        vec![("c", Var::Num(0.0))],
-        BasicNodeLogic::new(node_std_add),
+        STD_MATH_ADD.clone(),
+        vec![("a", PinType::Num), ("b", PinType::Num)],
+        vec![("c", PinType::Num)],
+        BasicNodeLogic::slots(node_std_add),
+        NodeMeta::new("Adds two numbers")
+            .with_category("math")
+            .with_tags(["+", "sum", "plus"])
+            .with_pin("c", "Result, an integer when both operands are integers"),
     );
     add_basic(
         registry,
-        GlobalName::from_path("std.math.subtract"),
-        vec![("a", Var::Num(0.0)), ("b", Var::Num(0.0))],
-        vec![("c", Var::Num(0.0))],
-        BasicNodeLogic::new(node_std_subtract),
+        STD_MATH_SUBTRACT.clone(),
+        vec![("a", PinType::Num), ("b", PinType::Num)],
+        vec![("c", PinType::Num)],
+        BasicNodeLogic::slots(node_std_subtract),
+        NodeMeta::new("Subtracts b from a")
+            .with_category("math")
+            .with_tags(["-", "minus", "difference"])
+            .with_pin("c", "Result, an integer when both operands are integers"),
     );
     add_basic(
         registry,
-        GlobalName::from_path("std.math.multiply"),
-        vec![("a", Var::Num(0.0)), ("b", Var::Num(0.0))],
-        vec![("c", Var::Num(0.0))],
-        BasicNodeLogic::new(node_std_multiply),
+        STD_MATH_MULTIPLY.clone(),
+        vec![("a", PinType::Num), ("b", PinType::Num)],
+        vec![("c", PinType::Num)],
+        BasicNodeLogic::slots(node_std_multiply),
+        NodeMeta::new("Multiplies two numbers")
+            .with_category("math")
+            .with_tags(["*", "times", "product"])
+            .with_pin("c", "Result, an integer when both operands are integers"),
     );
     add_basic(
         registry,
-        GlobalName::from_path("std.math.divide"),
-        vec![("a", Var::Num(0.0)), ("b", Var::Num(0.0))],
-        vec![("c", Var::Num(0.0))],
-        BasicNodeLogic::new(node_std_divide),
+        STD_MATH_DIVIDE.clone(),
+        vec![("a", PinType::Num), ("b", PinType::Num)],
+        vec![("c", PinType::Num)],
+        BasicNodeLogic::slots(node_std_divide),
+        NodeMeta::new("Divides a by b. Dividing by zero is an error")
+            .with_category("math")
+            .with_tags(["/", "quotient"])
+            .with_pin("c", "Result. Integer division truncates towards zero"),
     );
 }
 
-fn node_std_add(_env: Arc<Environment>, inputs: VarRegisters) -> Result<VarRegisters, NodeError> {
-    let name = GlobalName::from_path("std.math.add");
-    let a = get_var_number(&name, &inputs, "a".into())?;
-    let b = get_var_number(&name, &inputs, "b".into())?;
+/// Operands of a binary math node. Integers stay exact when both sides are integers, any float promotes the operation to floats
+enum Operands {
+    Int(i64, i64),
+    Num(f64, f64),
+}
+
+fn get_operands(name: &GlobalName, inputs: &SlotRegisters) -> Result<Operands, NodeError> {
+    let a = get_slot(name, inputs, A)?;
+    let b = get_slot(name, inputs, B)?;
+    if let (Var::Int(a), Var::Int(b)) = (a, b) {
+        return Ok(Operands::Int(*a, *b));
+    }
+    Ok(Operands::Num(
+        get_slot_number(name, inputs, A)?,
+        get_slot_number(name, inputs, B)?,
+    ))
+}
+
+fn math_op(
+    name: &GlobalName,
+    inputs: &SlotRegisters,
+    int_op: impl Fn(i64, i64) -> Option<i64>,
+    num_op: impl Fn(f64, f64) -> f64,
+) -> Result<VarRegisters, NodeError> {
+    let c = match get_operands(name, inputs)? {
+        Operands::Int(a, b) => {
+            let Some(c) = int_op(a, b) else {
+                return Err(NodeError::Overflow {
+                    name: name.clone(),
+                    msg: format!("Integer overflow with operands {a} and {b}"),
+                });
+            };
+            Var::Int(c)
+        }
+        Operands::Num(a, b) => Var::Num(num_op(a, b)),
+    };
     let mut out = VarRegisters::new();
-    out.0.insert("c".into(), Var::Num(a + b));
+    out.0.insert(C.clone(), c);
     Ok(out)
 }
 
+fn node_std_add(_env: Arc<Environment>, inputs: &SlotRegisters) -> Result<VarRegisters, NodeError> {
+    math_op(&STD_MATH_ADD, inputs, i64::checked_add, |a, b| a + b)
+}
+
 fn node_std_subtract(
     _env: Arc<Environment>,
-    inputs: VarRegisters,
+    inputs: &SlotRegisters,
 ) -> Result<VarRegisters, NodeError> {
-    let name = GlobalName::from_path("std.math.subtract");
-    let a = get_var_number(&name, &inputs, "a".into())?;
-    let b = get_var_number(&name, &inputs, "b".into())?;
-    let mut out = VarRegisters::new();
-    out.0.insert("c".into(), Var::Num(a - b));
-    Ok(out)
+    math_op(&STD_MATH_SUBTRACT, inputs, i64::checked_sub, |a, b| a - b)
 }
 
 fn node_std_multiply(
     _env: Arc<Environment>,
-    inputs: VarRegisters,
+    inputs: &SlotRegisters,
 ) -> Result<VarRegisters, NodeError> {
-    let name = GlobalName::from_path("std.math.multiply");
-    let a = get_var_number(&name, &inputs, "a".into())?;
-    let b = get_var_number(&name, &inputs, "b".into())?;
-    let mut out = VarRegisters::new();
-    out.0.insert("c".into(), Var::Num(a * b));
-    Ok(out)
+    math_op(&STD_MATH_MULTIPLY, inputs, i64::checked_mul, |a, b| a * b)
 }
 
+/// Integer division truncates towards zero, like it does in Rust
 fn node_std_divide(
     _env: Arc<Environment>,
-    inputs: VarRegisters,
+    inputs: &SlotRegisters,
 ) -> Result<VarRegisters, NodeError> {
-    let name = GlobalName::from_path("std.math.divide");
-    let a = get_var_number(&name, &inputs, "a".into())?;
-    let b = get_var_number(&name, &inputs, "b".into())?;
-    let mut out = VarRegisters::new();
-    if b == 0.0 {
+    let name = &*STD_MATH_DIVIDE;
+    if get_slot_number(name, inputs, B)? == 0.0 {
         return Err(NodeError::Unhandled(
             "Math error. Cannot divide by zero".into(),
         ));
     }
-    out.0.insert("c".into(), Var::Num(a / b));
-    Ok(out)
+    math_op(name, inputs, i64::checked_div, |a, b| a / b)
 }
 
 #[cfg(test)]
@@ -96,7 +152,7 @@ mod test {
 
     use crate::{
         nodes::NodeError,
-        types::{GlobalName, Var, VarRegisters},
+        types::{GlobalName, RegisterLayout, SlotRegisters, Var, VarRegisters},
         Environment,
     };
 
@@ -104,11 +160,12 @@ mod test {
     fn get_env() -> Arc<Environment> {
         Arc::new(Environment::new_empty())
     }
-    fn get_inputs(a: f64, b: f64) -> VarRegisters {
-        VarRegisters(HashMap::from([
-            ("a".into(), Var::Num(a)),
-            ("b".into(), Var::Num(b)),
-        ]))
+    fn get_inputs(a: Var, b: Var) -> SlotRegisters {
+        let layout = RegisterLayout::new(["a".into(), "b".into()]);
+        SlotRegisters::from_registers(
+            Arc::new(layout),
+            VarRegisters(HashMap::from([("a".into(), a), ("b".into(), b)])),
+        )
     }
 
     fn get_output(result: Result<VarRegisters, NodeError>) -> Result<f64, NodeError> {
@@ -139,11 +196,11 @@ mod test {
         a: f64,
         b: f64,
         c: f64,
-        func: impl Fn(Arc<Environment>, VarRegisters) -> Result<VarRegisters, NodeError>,
+        func: impl Fn(Arc<Environment>, &SlotRegisters) -> Result<VarRegisters, NodeError>,
     ) {
         eprintln!("Testing {a} (op) {b} = {c}");
 
-        let res = func(get_env(), get_inputs(a, b));
+        let res = func(get_env(), &get_inputs(Var::Num(a), Var::Num(b)));
         match get_output(res) {
             Ok(val) => assert_eq!(val, c),
             Err(err) => panic!("{:#?}", err),
@@ -186,4 +243,50 @@ mod test {
     fn test_div_by_zero() {
         test_math(1.0, 0.0, 0.0, node_std_divide);
     }
+
+    fn get_int_output(
+        a: i64,
+        b: i64,
+        func: fn(Arc<Environment>, &SlotRegisters) -> Result<VarRegisters, NodeError>,
+    ) -> Result<Var, NodeError> {
+        let res = func(get_env(), &get_inputs(Var::Int(a), Var::Int(b)))?;
+        Ok(res.0.get(&"c".into()).cloned().unwrap_or_default())
+    }
+
+    #[test]
+    fn test_int() {
+        assert_eq!(get_int_output(2, 3, node_std_add).unwrap(), Var::Int(5));
+        assert_eq!(
+            get_int_output(2, 3, node_std_subtract).unwrap(),
+            Var::Int(-1)
+        );
+        assert_eq!(
+            get_int_output(2, 3, node_std_multiply).unwrap(),
+            Var::Int(6)
+        );
+        assert_eq!(get_int_output(7, 2, node_std_divide).unwrap(), Var::Int(3));
+        // beyond 2^53 a float would have lost precision
+        assert_eq!(
+            get_int_output(i64::MAX - 1, 1, node_std_add).unwrap(),
+            Var::Int(i64::MAX)
+        );
+    }
+
+    #[test]
+    fn test_int_overflow() {
+        assert!(matches!(
+            get_int_output(i64::MAX, 1, node_std_add),
+            Err(NodeError::Overflow { .. })
+        ));
+        assert!(matches!(
+            get_int_output(i64::MIN, -1, node_std_divide),
+            Err(NodeError::Overflow { .. })
+        ));
+    }
+
+    #[test]
+    fn test_int_promotion() {
+        let res = node_std_add(get_env(), &get_inputs(Var::Int(2), Var::Num(0.5))).unwrap();
+        assert_eq!(res.0.get(&"c".into()).cloned(), Some(Var::Num(2.5)));
+    }
 }
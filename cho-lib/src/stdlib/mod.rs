@@ -1,13 +1,18 @@
 #![allow(unused)] // probably bad but I hate the warning and it'll take time to build the stdlib
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use crate::{
-    nodes::{BasicNode, BasicNodeLogic, Node, NodeError},
-    types::{GlobalName, StringName, TypeRegistry, Var, VarRegisters},
+    nodes::{BasicNode, BasicNodeLogic, Node, NodeError, NodeMeta},
+    types::{
+        GlobalName, PinRegisters, PinType, SlotRegisters, StringName, TypeRegistry, Var,
+        VarRegisters,
+    },
 };
 
 pub mod console;
 pub mod control;
+pub mod list;
+pub mod map;
 pub mod math;
 pub mod vars;
 
@@ -16,29 +21,44 @@ pub fn register(registry: &mut TypeRegistry<Node>) {
     math::register(registry);
     vars::register(registry);
     control::register(registry);
+    list::register(registry);
+    map::register(registry);
 }
 
 fn add_basic(
     reg: &mut TypeRegistry<Node>,
     name: GlobalName,
-    inputs: impl IntoIterator<Item = (&'static str, Var)>,
-    outputs: impl IntoIterator<Item = (&'static str, Var)>,
+    inputs: impl IntoIterator<Item = (&'static str, PinType)>,
+    outputs: impl IntoIterator<Item = (&'static str, PinType)>,
     logic: BasicNodeLogic,
+    meta: NodeMeta,
 ) {
-    let mut input_map = VarRegisters::new();
-    let mut output_map = VarRegisters::new();
+    reg.register(Node::Basic(basic(name, inputs, outputs, logic, meta)));
+}
+
+fn basic(
+    name: GlobalName,
+    inputs: impl IntoIterator<Item = (&'static str, PinType)>,
+    outputs: impl IntoIterator<Item = (&'static str, PinType)>,
+    logic: BasicNodeLogic,
+    meta: NodeMeta,
+) -> BasicNode {
+    let mut input_map = PinRegisters::new();
+    let mut output_map = PinRegisters::new();
     for (k, v) in inputs.into_iter() {
         input_map.0.insert(k.into(), v);
     }
     for (k, v) in outputs.into_iter() {
         output_map.0.insert(k.into(), v);
     }
-    reg.register(Node::Basic(BasicNode {
+    BasicNode {
         name,
         logic,
         inputs: input_map,
         outputs: output_map,
-    }));
+        meta,
+        volatile: false,
+    }
 }
 
 fn get_var(
@@ -51,13 +71,56 @@ fn get_var(
     if var == Var::Null {
         return Err(NodeError::NullException {
             name: name.clone(),
-            arg: sn.into(),
+            arg: sn,
             msg: "Field was found null".into(),
         });
     }
     Ok(var)
 }
 
+/// Like [get_var], for nodes reading their inputs by slot
+fn get_slot<'a>(
+    name: &GlobalName,
+    inputs: &'a SlotRegisters,
+    slot: usize,
+) -> Result<&'a Var, NodeError> {
+    let var = inputs.get(slot);
+    if *var == Var::Null {
+        return Err(NodeError::NullException {
+            name: name.clone(),
+            arg: slot_name(inputs, slot),
+            msg: "Field was found null".into(),
+        });
+    }
+    Ok(var)
+}
+
+fn get_slot_number(
+    name: &GlobalName,
+    inputs: &SlotRegisters,
+    slot: usize,
+) -> Result<f64, NodeError> {
+    let var = get_slot(name, inputs, slot)?;
+    let Some(value) = var.as_number() else {
+        return Err(NodeError::MismatchedData {
+            name: name.clone(),
+            arg: slot_name(inputs, slot),
+            expected: Var::Num(Default::default()),
+            received: var.clone(),
+            msg: "".into(),
+        });
+    };
+    Ok(value)
+}
+
+fn slot_name(inputs: &SlotRegisters, slot: usize) -> StringName {
+    inputs
+        .layout()
+        .name(slot)
+        .cloned()
+        .unwrap_or_else(|| slot.to_string().into())
+}
+
 fn get_var_string(
     name: &GlobalName,
     inputs: &VarRegisters,
@@ -100,7 +163,7 @@ fn get_var_number(
     field: StringName,
 ) -> Result<f64, NodeError> {
     let var = get_var(name, inputs, field.clone())?;
-    let Var::Num(value) = var else {
+    let Some(value) = var.as_number() else {
         return Err(NodeError::MismatchedData {
             name: name.clone(),
             arg: field,
@@ -111,3 +174,73 @@ fn get_var_number(
     };
     Ok(value)
 }
+
+fn get_var_int(
+    name: &GlobalName,
+    inputs: &VarRegisters,
+    field: StringName,
+) -> Result<i64, NodeError> {
+    let var = get_var(name, inputs, field.clone())?;
+    let Some(value) = var.as_int() else {
+        return Err(NodeError::MismatchedData {
+            name: name.clone(),
+            arg: field,
+            expected: Var::Int(Default::default()),
+            received: var,
+            msg: "Expected a whole number".into(),
+        });
+    };
+    Ok(value)
+}
+
+fn get_var_list(
+    name: &GlobalName,
+    inputs: &VarRegisters,
+    field: StringName,
+) -> Result<Vec<Var>, NodeError> {
+    let var = get_var(name, inputs, field.clone())?;
+    let Var::List(value) = var else {
+        return Err(NodeError::MismatchedData {
+            name: name.clone(),
+            arg: field,
+            expected: Var::List(Default::default()),
+            received: var,
+            msg: "".into(),
+        });
+    };
+    Ok(value)
+}
+
+fn get_var_map(
+    name: &GlobalName,
+    inputs: &VarRegisters,
+    field: StringName,
+) -> Result<BTreeMap<String, Var>, NodeError> {
+    let var = get_var(name, inputs, field.clone())?;
+    let Var::Map(value) = var else {
+        return Err(NodeError::MismatchedData {
+            name: name.clone(),
+            arg: field,
+            expected: Var::Map(Default::default()),
+            received: var,
+            msg: "".into(),
+        });
+    };
+    Ok(value)
+}
+
+/// Reads a number which is used to index into a collection, so it has to be a non-negative whole number
+fn get_var_index(
+    name: &GlobalName,
+    inputs: &VarRegisters,
+    field: StringName,
+) -> Result<usize, NodeError> {
+    let value = get_var_int(name, inputs, field.clone())?;
+    usize::try_from(value).map_err(|_| NodeError::MismatchedData {
+        name: name.clone(),
+        arg: field,
+        expected: Var::Int(Default::default()),
+        received: Var::Int(value),
+        msg: "Index must not be negative".into(),
+    })
+}
@@ -3,21 +3,156 @@ use std::{collections::HashMap, sync::Arc};
 use serde::Deserialize;
 
 use crate::{
-    nodes::{BasicNodeLogic, Node, NodeError},
-    types::{GlobalName, StringName, TypeRegistry, Var, VarRegisters},
+    nodes::{BasicNodeLogic, Node, NodeError, NodeMeta},
+    types::{GlobalName, PinType, StringName, TypeRegistry, Var, VarRegisters},
     Environment,
 };
 
-use super::{add_basic, get_var_number, get_var_string};
+use super::{add_basic, basic, get_var_number, get_var_string};
 
 pub fn register(registry: &mut TypeRegistry<Node>) {
     add_basic(
         registry,
         GlobalName::from_path("std.vars.value"),
-        vec![("value", Var::String("".into()))],
-        vec![("var", Var::Null)],
+        vec![("value", PinType::String)],
+        vec![("var", PinType::Any)],
         BasicNodeLogic::new(node_var),
+        NodeMeta::new("Produces a constant value parsed from RON text")
+            .with_category("vars")
+            .with_tags(["constant", "literal"])
+            .with_pin("value", "RON representation of the value, e.g. `Num(1.0)`")
+            .with_pin("var", "The parsed value, or null if it failed to parse"),
     );
+
+    let mut get = basic(
+        GlobalName::from_path("std.vars.get"),
+        vec![("name", PinType::String)],
+        vec![("value", PinType::Any)],
+        BasicNodeLogic::new(node_std_vars_get),
+        NodeMeta::new("Reads a global variable of the running execution")
+            .with_category("vars")
+            .with_tags(["global", "variable", "read"])
+            .with_pin("name", "Name of the variable")
+            .with_pin(
+                "value",
+                "The current value, or null if the variable is not set",
+            ),
+    );
+    get.volatile = true;
+    registry.register(Node::Basic(get));
+
+    let mut has = basic(
+        GlobalName::from_path("std.vars.has"),
+        vec![("name", PinType::String)],
+        vec![("has", PinType::Bool)],
+        BasicNodeLogic::new(node_std_vars_has),
+        NodeMeta::new("Checks whether a global variable is set")
+            .with_category("vars")
+            .with_tags(["global", "variable", "exists"])
+            .with_pin("name", "Name of the variable"),
+    );
+    has.volatile = true;
+    registry.register(Node::Basic(has));
+
+    add_basic(
+        registry,
+        GlobalName::from_path("std.vars.set"),
+        vec![
+            ("exec", PinType::Exec),
+            ("name", PinType::String),
+            ("value", PinType::Any),
+        ],
+        vec![("then", PinType::Exec), ("previous", PinType::Any)],
+        BasicNodeLogic::new(node_std_vars_set),
+        NodeMeta::new("Writes a global variable of the running execution, creating it if needed")
+            .with_category("vars")
+            .with_tags(["global", "variable", "write", "assign"])
+            .with_pin("name", "Name of the variable")
+            .with_pin(
+                "previous",
+                "The value it replaced, or null if it was not set",
+            ),
+    );
+
+    add_basic(
+        registry,
+        GlobalName::from_path("std.vars.delete"),
+        vec![("exec", PinType::Exec), ("name", PinType::String)],
+        vec![("then", PinType::Exec), ("previous", PinType::Any)],
+        BasicNodeLogic::new(node_std_vars_delete),
+        NodeMeta::new("Removes a global variable of the running execution")
+            .with_category("vars")
+            .with_tags(["global", "variable", "remove", "unset"])
+            .with_pin("name", "Name of the variable")
+            .with_pin("previous", "The removed value, or null if it was not set"),
+    );
+}
+
+fn node_std_vars_get(
+    env: Arc<Environment>,
+    inputs: VarRegisters,
+) -> Result<VarRegisters, NodeError> {
+    let name = get_var_string(
+        &GlobalName::from_path("std.vars.get"),
+        &inputs,
+        "name".into(),
+    )?;
+    let mut out = VarRegisters::new();
+    // a name which was never interned can not have been set, and looking it up keeps it from being interned
+    let value = StringName::lookup(&name).and_then(|name| env.globals.get(&name));
+    out.0.insert("value".into(), value.unwrap_or_default());
+    Ok(out)
+}
+
+fn node_std_vars_has(
+    env: Arc<Environment>,
+    inputs: VarRegisters,
+) -> Result<VarRegisters, NodeError> {
+    let name = get_var_string(
+        &GlobalName::from_path("std.vars.has"),
+        &inputs,
+        "name".into(),
+    )?;
+    let mut out = VarRegisters::new();
+    let has = StringName::lookup(&name).is_some_and(|name| env.globals.has(&name));
+    out.0.insert("has".into(), Var::Bool(has));
+    Ok(out)
+}
+
+fn node_std_vars_set(
+    env: Arc<Environment>,
+    inputs: VarRegisters,
+) -> Result<VarRegisters, NodeError> {
+    let name = get_var_string(
+        &GlobalName::from_path("std.vars.set"),
+        &inputs,
+        "name".into(),
+    )?;
+    let value = inputs.0.get(&"value".into()).cloned().unwrap_or_default();
+    let mut out = VarRegisters::new();
+    out.0.insert(
+        "previous".into(),
+        env.globals.set(name.into(), value).unwrap_or_default(),
+    );
+    out.0.insert("then".into(), Var::Execution(true));
+    Ok(out)
+}
+
+fn node_std_vars_delete(
+    env: Arc<Environment>,
+    inputs: VarRegisters,
+) -> Result<VarRegisters, NodeError> {
+    let name = get_var_string(
+        &GlobalName::from_path("std.vars.delete"),
+        &inputs,
+        "name".into(),
+    )?;
+    let mut out = VarRegisters::new();
+    let previous = StringName::lookup(&name).and_then(|name| env.globals.delete(&name));
+    out.0
+        .insert("previous".into(), previous.unwrap_or_default());
+    out.0.insert("then".into(), Var::Execution(true));
+    Ok(out)
 }
 
 fn node_var(_env: Arc<Environment>, inputs: VarRegisters) -> Result<VarRegisters, NodeError> {
@@ -25,9 +160,67 @@ fn node_var(_env: Arc<Environment>, inputs: VarRegisters) -> Result<VarRegisters
     let type_value = get_var_string(&name, &inputs, "value".into())?;
     let var_name = get_var_string(&name, &inputs, "name".into()).unwrap_or("var".into());
     let mut out = VarRegisters::new();
-    out.0.insert(
-        var_name.into(),
-        ron::from_str(&type_value).unwrap_or_default(),
-    );
+    // no pin can read a name which was never interned, so there is nothing to output
+    if let Some(var_name) = StringName::lookup(&var_name) {
+        out.0
+            .insert(var_name, ron::from_str(&type_value).unwrap_or_default());
+    }
     Ok(out)
 }
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, sync::Arc};
+
+    use crate::{
+        types::{Globals, StringName, Var, VarRegisters},
+        Environment,
+    };
+
+    use super::{node_std_vars_delete, node_std_vars_get, node_std_vars_has, node_std_vars_set};
+
+    fn named(name: &str) -> VarRegisters {
+        VarRegisters(HashMap::from([("name".into(), Var::String(name.into()))]))
+    }
+
+    #[test]
+    fn test_globals() {
+        let mut env = Environment::new_empty();
+        env.globals = Globals::new(VarRegisters(HashMap::from([(
+            "score".into(),
+            Var::Num(1.0),
+        )])));
+        let env = Arc::new(env);
+
+        let res = node_std_vars_get(env.clone(), named("score")).unwrap();
+        assert_eq!(res.0.get(&"value".into()), Some(&Var::Num(1.0)));
+
+        let mut inputs = named("score");
+        inputs.0.insert("value".into(), Var::Num(5.0));
+        let res = node_std_vars_set(env.clone(), inputs).unwrap();
+        assert_eq!(res.0.get(&"previous".into()), Some(&Var::Num(1.0)));
+        assert_eq!(res.0.get(&"then".into()), Some(&Var::Execution(true)));
+
+        // clones of the environment share the same variables
+        let shared = Arc::new((*env).clone());
+        let res = node_std_vars_get(shared.clone(), named("score")).unwrap();
+        assert_eq!(res.0.get(&"value".into()), Some(&Var::Num(5.0)));
+
+        let res = node_std_vars_delete(shared, named("score")).unwrap();
+        assert_eq!(res.0.get(&"previous".into()), Some(&Var::Num(5.0)));
+        let res = node_std_vars_has(env.clone(), named("score")).unwrap();
+        assert_eq!(res.0.get(&"has".into()), Some(&Var::Bool(false)));
+        let res = node_std_vars_get(env.clone(), named("score")).unwrap();
+        assert_eq!(res.0.get(&"value".into()), Some(&Var::Null));
+
+        // names which were never set are not interned by reading them
+        let unseen = "vars.test.unseen";
+        let res = node_std_vars_get(env.clone(), named(unseen)).unwrap();
+        assert_eq!(res.0.get(&"value".into()), Some(&Var::Null));
+        let res = node_std_vars_has(env.clone(), named(unseen)).unwrap();
+        assert_eq!(res.0.get(&"has".into()), Some(&Var::Bool(false)));
+        let res = node_std_vars_delete(env, named(unseen)).unwrap();
+        assert_eq!(res.0.get(&"previous".into()), Some(&Var::Null));
+        assert_eq!(StringName::lookup(unseen), None);
+    }
+}
@@ -0,0 +1,404 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use crate::{
+    nodes::{BasicNode, BasicNodeLogic, Node, NodeError, NodeMeta},
+    types::{
+        GlobalName, NamespacedType, PinRegisters, PinType, StringName, StructVar, Var, VarRegisters,
+    },
+};
+
+/// A user defined record type. Values of this type are passed between nodes as a single [Var::Struct]
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructDef {
+    pub name: GlobalName,
+    /// Fields in declaration order
+    pub fields: Vec<(StringName, PinType)>,
+}
+
+impl NamespacedType for StructDef {
+    fn get_name(&self) -> GlobalName {
+        self.name.clone()
+    }
+}
+
+impl StructDef {
+    pub const MAKE: &str = "make";
+    pub const BREAK: &str = "break";
+    pub const GET_FIELD: &str = "get_field";
+    pub const SET_FIELD: &str = "set_field";
+
+    pub fn new(
+        name: GlobalName,
+        fields: impl IntoIterator<Item = (impl Into<StringName>, PinType)>,
+    ) -> Self {
+        Self {
+            name,
+            fields: fields
+                .into_iter()
+                .map(|(key, pin)| (key.into(), pin))
+                .collect(),
+        }
+    }
+
+    /// The pin type of values of this struct
+    pub fn pin_type(&self) -> PinType {
+        PinType::Struct(self.name.to_path())
+    }
+
+    /// Name of a node generated for this struct, e.g. `game.Player.make`
+    pub fn node_name(&self, node: &str) -> GlobalName {
+        GlobalName::from_path(format!("{}.{}", self.name.to_path(), node))
+    }
+
+    /// Generates the `make`, `break`, `get_field` and `set_field` nodes for this struct
+    pub fn generate_nodes(&self) -> Vec<Node> {
+        let def = Arc::new(self.clone());
+        let type_name = self.name.1 .0.to_string();
+
+        let mut field_pins = PinRegisters::new();
+        for (key, pin) in self.fields.iter() {
+            field_pins.0.insert(key.clone(), pin.clone());
+        }
+        let struct_pin = PinRegisters([("struct".into(), self.pin_type())].into_iter().collect());
+
+        let make_def = def.clone();
+        let break_def = def.clone();
+        let get_def = def.clone();
+        let set_def = def.clone();
+        vec![
+            Node::Basic(BasicNode {
+                name: self.node_name(Self::MAKE),
+                inputs: field_pins.clone(),
+                outputs: struct_pin.clone(),
+                logic: BasicNodeLogic::new(move |_, inputs| make_def.make(inputs)),
+                meta: self
+                    .meta(format!("Builds a {} from its fields", type_name))
+                    .with_display_name(format!("Make {}", type_name)),
+                volatile: false,
+            }),
+            Node::Basic(BasicNode {
+                name: self.node_name(Self::BREAK),
+                inputs: struct_pin.clone(),
+                outputs: field_pins,
+                logic: BasicNodeLogic::new(move |_, inputs| break_def.break_value(inputs)),
+                meta: self
+                    .meta(format!("Splits a {} into its fields", type_name))
+                    .with_display_name(format!("Break {}", type_name)),
+                volatile: false,
+            }),
+            Node::Basic(BasicNode {
+                name: self.node_name(Self::GET_FIELD),
+                inputs: PinRegisters(
+                    [
+                        ("struct".into(), self.pin_type()),
+                        ("field".into(), PinType::String),
+                    ]
+                    .into_iter()
+                    .collect(),
+                ),
+                outputs: PinRegisters([("value".into(), PinType::Any)].into_iter().collect()),
+                logic: BasicNodeLogic::new(move |_, inputs| get_def.get_field(inputs)),
+                meta: self
+                    .meta(format!("Reads one field of a {} by name", type_name))
+                    .with_pin("field", "Name of the field to read"),
+                volatile: false,
+            }),
+            Node::Basic(BasicNode {
+                name: self.node_name(Self::SET_FIELD),
+                inputs: PinRegisters(
+                    [
+                        ("struct".into(), self.pin_type()),
+                        ("field".into(), PinType::String),
+                        ("value".into(), PinType::Any),
+                    ]
+                    .into_iter()
+                    .collect(),
+                ),
+                outputs: struct_pin,
+                logic: BasicNodeLogic::new(move |_, inputs| set_def.set_field(inputs)),
+                meta: self
+                    .meta(format!("Copies a {} with one field replaced", type_name))
+                    .with_pin("field", "Name of the field to replace")
+                    .with_pin("value", "New value, which must match the type of the field"),
+                volatile: false,
+            }),
+        ]
+    }
+
+    /// Metadata shared by every generated node
+    fn meta(&self, description: String) -> NodeMeta {
+        NodeMeta::new(description)
+            .with_category("struct")
+            .with_tags(["struct"])
+    }
+
+    /// A value of this struct with every field set to the default of its type
+    pub fn default_value(&self) -> StructVar {
+        StructVar {
+            type_name: self.name.to_path(),
+            fields: self
+                .fields
+                .iter()
+                .map(|(key, pin)| (key.to_string(), pin.default_value()))
+                .collect(),
+        }
+    }
+
+    fn make(&self, inputs: VarRegisters) -> Result<VarRegisters, NodeError> {
+        let name = self.node_name(Self::MAKE);
+        let mut fields = BTreeMap::new();
+        for (key, pin) in self.fields.iter() {
+            let value = inputs.0.get(key).cloned().unwrap_or_default();
+            self.check_field(&name, key, pin, &value)?;
+            fields.insert(key.to_string(), value);
+        }
+        Ok(VarRegisters(
+            [(
+                "struct".into(),
+                Var::Struct(Box::new(StructVar {
+                    type_name: self.name.to_path(),
+                    fields,
+                })),
+            )]
+            .into_iter()
+            .collect(),
+        ))
+    }
+
+    fn break_value(&self, inputs: VarRegisters) -> Result<VarRegisters, NodeError> {
+        let name = self.node_name(Self::BREAK);
+        let value = self.get_struct(&name, &inputs)?;
+        let mut out = VarRegisters::new();
+        for (key, _) in self.fields.iter() {
+            out.0.insert(
+                key.clone(),
+                value
+                    .fields
+                    .get(&key.to_string())
+                    .cloned()
+                    .unwrap_or_default(),
+            );
+        }
+        Ok(out)
+    }
+
+    fn get_field(&self, inputs: VarRegisters) -> Result<VarRegisters, NodeError> {
+        let name = self.node_name(Self::GET_FIELD);
+        let value = self.get_struct(&name, &inputs)?;
+        let (key, _) = self.get_field_def(&name, &inputs)?;
+        let field = value
+            .fields
+            .get(&key.to_string())
+            .cloned()
+            .unwrap_or_default();
+        Ok(VarRegisters(
+            [("value".into(), field)].into_iter().collect(),
+        ))
+    }
+
+    fn set_field(&self, inputs: VarRegisters) -> Result<VarRegisters, NodeError> {
+        let name = self.node_name(Self::SET_FIELD);
+        let mut value = self.get_struct(&name, &inputs)?;
+        let (key, pin) = self.get_field_def(&name, &inputs)?;
+        let field = inputs.0.get(&"value".into()).cloned().unwrap_or_default();
+        self.check_field(&name, &key, &pin, &field)?;
+        value.fields.insert(key.to_string(), field);
+        Ok(VarRegisters(
+            [("struct".into(), Var::Struct(Box::new(value)))]
+                .into_iter()
+                .collect(),
+        ))
+    }
+
+    fn get_struct(&self, name: &GlobalName, inputs: &VarRegisters) -> Result<StructVar, NodeError> {
+        let var = inputs.0.get(&"struct".into()).cloned().unwrap_or_default();
+        match var {
+            Var::Struct(value) if value.type_name == self.name.to_path() => Ok(*value),
+            Var::Null => Err(NodeError::NullException {
+                name: name.clone(),
+                arg: "struct".into(),
+                msg: "Field was found null".into(),
+            }),
+            received => Err(NodeError::MismatchedData {
+                name: name.clone(),
+                arg: "struct".into(),
+                expected: Var::Struct(Box::new(self.default_value())),
+                received,
+                msg: format!("Expected a {:?} struct", self.name),
+            }),
+        }
+    }
+
+    fn get_field_def(
+        &self,
+        name: &GlobalName,
+        inputs: &VarRegisters,
+    ) -> Result<(StringName, PinType), NodeError> {
+        let field = match inputs.0.get(&"field".into()) {
+            Some(Var::String(field)) => field.clone(),
+            other => {
+                return Err(NodeError::MismatchedData {
+                    name: name.clone(),
+                    arg: "field".into(),
+                    expected: Var::String(Default::default()),
+                    received: other.cloned().unwrap_or_default(),
+                    msg: "".into(),
+                })
+            }
+        };
+        self.fields
+            .iter()
+            .find(|(key, _)| key.as_str() == field)
+            .cloned()
+            .ok_or_else(|| NodeError::TypeNotFound {
+                name: name.clone(),
+                msg: format!("Struct {:?} has no field {:?}", self.name, field),
+            })
+    }
+
+    fn check_field(
+        &self,
+        name: &GlobalName,
+        key: &StringName,
+        pin: &PinType,
+        value: &Var,
+    ) -> Result<(), NodeError> {
+        if *value == Var::Null {
+            return Err(NodeError::NullException {
+                name: name.clone(),
+                arg: key.clone(),
+                msg: "Struct fields cannot be null".into(),
+            });
+        }
+        if !pin.matches(value) {
+            return Err(NodeError::MismatchedData {
+                name: name.clone(),
+                arg: key.clone(),
+                expected: pin.default_value(),
+                received: value.clone(),
+                msg: format!("Mismatched type for field of struct {:?}", self.name),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, sync::Arc};
+
+    use crate::{
+        nodes::NodeData,
+        types::{GlobalName, NamespacedType, PinType, RegistryError, Var, VarRegisters},
+        Environment,
+    };
+
+    use super::StructDef;
+
+    fn get_env() -> Environment {
+        let mut env = Environment::new_empty();
+        env.register_struct(StructDef::new(
+            GlobalName::from_path("game.Player"),
+            [("hp", PinType::Num), ("name", PinType::String)],
+        ))
+        .unwrap();
+        env
+    }
+
+    fn run(env: &Environment, node: &str, inputs: VarRegisters) -> VarRegisters {
+        let node = env
+            .nodes
+            .get(&GlobalName::from_path(node))
+            .expect("generated node");
+        node.execute(Arc::new(env.clone()), inputs).unwrap()
+    }
+
+    #[test]
+    fn test_struct_nodes() {
+        let env = get_env();
+        assert!(env.structs.contains(&GlobalName::from_path("game.Player")));
+
+        let made = run(
+            &env,
+            "game.Player.make",
+            VarRegisters(HashMap::from([
+                ("hp".into(), Var::Num(10.0)),
+                ("name".into(), Var::String("hero".into())),
+            ])),
+        );
+        let player = made.0.get(&"struct".into()).cloned().unwrap();
+
+        let set = run(
+            &env,
+            "game.Player.set_field",
+            VarRegisters(HashMap::from([
+                ("struct".into(), player),
+                ("field".into(), Var::String("hp".into())),
+                ("value".into(), Var::Num(3.0)),
+            ])),
+        );
+        let player = set.0.get(&"struct".into()).cloned().unwrap();
+
+        let fields = run(
+            &env,
+            "game.Player.break",
+            VarRegisters(HashMap::from([("struct".into(), player.clone())])),
+        );
+        assert_eq!(fields.0.get(&"hp".into()).cloned(), Some(Var::Num(3.0)));
+        assert_eq!(
+            fields.0.get(&"name".into()).cloned(),
+            Some(Var::String("hero".into()))
+        );
+
+        let field = run(
+            &env,
+            "game.Player.get_field",
+            VarRegisters(HashMap::from([
+                ("struct".into(), player),
+                ("field".into(), Var::String("name".into())),
+            ])),
+        );
+        assert_eq!(
+            field.0.get(&"value".into()).cloned(),
+            Some(Var::String("hero".into()))
+        );
+    }
+
+    #[test]
+    fn test_struct_field_types() {
+        let env = get_env();
+        let node = env
+            .nodes
+            .get(&GlobalName::from_path("game.Player.make"))
+            .unwrap();
+        let res = node.execute(
+            Arc::new(env.clone()),
+            VarRegisters(HashMap::from([
+                ("hp".into(), Var::String("lots".into())),
+                ("name".into(), Var::String("hero".into())),
+            ])),
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_register_collision() {
+        let mut env = get_env();
+        let def = StructDef::new(GlobalName::from_path("game.Item"), [("id", PinType::Int)]);
+        // something else already took the name of the third generated node
+        let taken = def.generate_nodes().remove(2);
+        assert_eq!(taken.get_name(), def.node_name(StructDef::GET_FIELD));
+        env.nodes.register(taken);
+
+        assert_eq!(
+            env.register_struct(def.clone()),
+            Err(RegistryError::Duplicate(
+                def.node_name(StructDef::GET_FIELD)
+            ))
+        );
+        // nothing of the struct was left behind
+        assert!(!env.structs.contains(&def.name));
+        assert!(!env.nodes.contains(&def.node_name(StructDef::MAKE)));
+        assert!(!env.nodes.contains(&def.node_name(StructDef::BREAK)));
+    }
+}
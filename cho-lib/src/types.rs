@@ -1,9 +1,23 @@
-use std::{collections::HashMap, fmt::Debug, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    sync::{Arc, OnceLock, PoisonError, RwLock},
+};
 
 use serde::{Deserialize, Serialize};
 
-#[derive(PartialEq, Eq, Hash, Clone)]
-pub struct StringName(Arc<Vec<u8>>);
+use crate::logger::Logger;
+
+/// An interned name. Equal names share one allocation, so comparing and hashing them only looks at the pointer.
+///
+/// Interned names are never freed, so names should not be built from unbounded data. Look those up with [StringName::lookup] instead
+#[derive(Clone)]
+pub struct StringName(Arc<String>);
+
+/// Entry of the name interner, looked up by the text of the name
+#[derive(PartialEq, Eq, Hash)]
+struct Interned(Arc<String>);
 
 #[derive(PartialEq, Eq, Hash, Clone, Debug)]
 pub struct Namespace(pub StringName);
@@ -14,14 +28,62 @@ pub struct TypeName(pub StringName);
 #[derive(PartialEq, Clone, Debug, Default)]
 pub struct VarRegisters(pub HashMap<StringName, Var>);
 
+/// The order in which the pins on one side of a node are stored in [SlotRegisters]. Pins are sorted by name
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct RegisterLayout(Vec<StringName>);
+
+/// Values of pins stored by their slot in a [RegisterLayout], so reading them needs no hashing. Pins without a value hold [Var::Null]
+#[derive(PartialEq, Clone)]
+pub struct SlotRegisters {
+    layout: Arc<RegisterLayout>,
+    values: Vec<Var>,
+}
+
+/// Named variables shared by every node of an execution. Clones share the same storage
+#[derive(Clone, Debug, Default)]
+pub struct Globals(Arc<RwLock<HashMap<StringName, Var>>>);
+
+/// The declared type of every pin on one side of a node
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct PinRegisters(pub HashMap<StringName, PinType>);
+
+/// The type of value a pin carries
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Default, Serialize, Deserialize)]
+pub enum PinType {
+    #[default]
+    Any,
+    Exec,
+    Num,
+    Int,
+    Bool,
+    String,
+    List(Box<PinType>),
+    Map(Box<PinType>),
+    /// A [Var::Struct] of the struct type with this path
+    Struct(String),
+}
+
 #[derive(Clone, PartialEq, Default, Debug, Serialize, Deserialize)]
 pub enum Var {
     #[default]
     Null,
     Num(f64),
+    Int(i64),
     Bool(bool),
     String(String),
     Execution(bool),
+    List(Vec<Var>),
+    /// Ordered by key, so that serialized maps are stable
+    Map(BTreeMap<String, Var>),
+    Struct(Box<StructVar>),
+}
+
+/// A value of a user defined struct type, see [crate::structs::StructDef]
+#[derive(Clone, PartialEq, Default, Debug, Serialize, Deserialize)]
+pub struct StructVar {
+    /// Path of the [crate::structs::StructDef] this value was made from
+    pub type_name: String,
+    pub fields: BTreeMap<String, Var>,
 }
 
 #[derive(PartialEq, Eq, Hash, Clone)]
@@ -35,6 +97,29 @@ pub trait NamespacedType {
 #[derive(Debug, Clone)]
 pub struct TypeRegistry<T: NamespacedType + Clone> {
     types: HashMap<Namespace, HashMap<TypeName, Arc<T>>>,
+    /// Entries hidden by a newer registration under [RegistryPolicy::ShadowWithWarning], oldest first
+    shadowed: HashMap<GlobalName, Vec<Arc<T>>>,
+    policy: RegistryPolicy,
+    /// Where warnings about shadowed entries go. Registries of an [crate::Environment] use its logger
+    logger: Option<Logger>,
+}
+
+/// What [TypeRegistry::try_register] does when a name is already taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RegistryPolicy {
+    /// Refuse the new entry
+    #[default]
+    Reject,
+    /// Replace the existing entry, discarding it
+    Override,
+    /// Replace the existing entry with a warning, restoring it if the new entry is unregistered
+    ShadowWithWarning,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegistryError {
+    Duplicate(GlobalName),
+    NotFound(GlobalName),
 }
 
 impl VarRegisters {
@@ -43,27 +128,439 @@ impl VarRegisters {
     }
 }
 
+impl RegisterLayout {
+    pub fn new(names: impl IntoIterator<Item = StringName>) -> Self {
+        let mut names = names.into_iter().collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+        Self(names)
+    }
+
+    /// Adds pins after the sorted ones, keeping the slots of every existing pin
+    pub fn extend(&mut self, names: impl IntoIterator<Item = StringName>) {
+        for name in names {
+            if !self.0.contains(&name) {
+                self.0.push(name);
+            }
+        }
+    }
+
+    pub fn slot(&self, name: &StringName) -> Option<usize> {
+        self.0.iter().position(|pin| pin == name)
+    }
+
+    pub fn name(&self, slot: usize) -> Option<&StringName> {
+        self.0.get(slot)
+    }
+
+    pub fn names(&self) -> &[StringName] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl SlotRegisters {
+    /// Registers with every slot empty
+    pub fn new(layout: Arc<RegisterLayout>) -> Self {
+        Self {
+            values: vec![Var::Null; layout.len()],
+            layout,
+        }
+    }
+
+    /// Lays out named registers. Names the layout has no slot for are dropped
+    pub fn from_registers(layout: Arc<RegisterLayout>, mut registers: VarRegisters) -> Self {
+        let values = layout
+            .names()
+            .iter()
+            .map(|name| registers.0.remove(name).unwrap_or_default())
+            .collect();
+        Self { layout, values }
+    }
+
+    pub fn layout(&self) -> &RegisterLayout {
+        &self.layout
+    }
+
+    /// The value in `slot`, which is [Var::Null] for empty or unknown slots
+    pub fn get(&self, slot: usize) -> &Var {
+        const NULL: &Var = &Var::Null;
+        self.values.get(slot).unwrap_or(NULL)
+    }
+
+    pub fn get_named(&self, name: &StringName) -> &Var {
+        self.layout
+            .slot(name)
+            .map_or(&Var::Null, |slot| self.get(slot))
+    }
+
+    pub fn set(&mut self, slot: usize, value: Var) {
+        if let Some(entry) = self.values.get_mut(slot) {
+            *entry = value;
+        }
+    }
+
+    /// Named registers of every slot holding a value
+    pub fn to_registers(&self) -> VarRegisters {
+        VarRegisters(
+            self.layout
+                .names()
+                .iter()
+                .zip(self.values.iter())
+                .filter(|(_, value)| **value != Var::Null)
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+        )
+    }
+}
+
+impl Debug for SlotRegisters {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map()
+            .entries(self.layout.names().iter().zip(self.values.iter()))
+            .finish()
+    }
+}
+
+impl Globals {
+    /// Separate storage starting with `initial`
+    pub fn new(initial: VarRegisters) -> Self {
+        Self(Arc::new(RwLock::new(initial.0)))
+    }
+
+    // a writer which panicked can only have left a whole value behind, so poisoning is ignored
+    pub fn get(&self, name: &StringName) -> Option<Var> {
+        let vars = self.0.read().unwrap_or_else(PoisonError::into_inner);
+        vars.get(name).cloned()
+    }
+
+    pub fn has(&self, name: &StringName) -> bool {
+        let vars = self.0.read().unwrap_or_else(PoisonError::into_inner);
+        vars.contains_key(name)
+    }
+
+    /// Returns the previous value, if there was one
+    pub fn set(&self, name: StringName, value: Var) -> Option<Var> {
+        let mut vars = self.0.write().unwrap_or_else(PoisonError::into_inner);
+        vars.insert(name, value)
+    }
+
+    /// Returns the removed value, if there was one
+    pub fn delete(&self, name: &StringName) -> Option<Var> {
+        let mut vars = self.0.write().unwrap_or_else(PoisonError::into_inner);
+        vars.remove(name)
+    }
+
+    /// A copy of every variable as it is now
+    pub fn snapshot(&self) -> VarRegisters {
+        let vars = self.0.read().unwrap_or_else(PoisonError::into_inner);
+        VarRegisters(vars.clone())
+    }
+}
+
+impl PinRegisters {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Names of all pins which carry execution flow rather than data
+    pub fn exec_pins(&self) -> Vec<StringName> {
+        self.0
+            .iter()
+            .filter(|(_, pin)| **pin == PinType::Exec)
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+}
+
+impl PinType {
+    pub fn list_of(item: PinType) -> Self {
+        PinType::List(Box::new(item))
+    }
+
+    pub fn map_of(value: PinType) -> Self {
+        PinType::Map(Box::new(value))
+    }
+
+    /// Whether a connection from a pin of type `from` into a pin of this type is valid.
+    ///
+    /// Numbers are interchangeable here since math nodes produce either depending on their operands, so integer pins verify their values at runtime instead
+    pub fn accepts(&self, from: &PinType) -> bool {
+        match (self, from) {
+            (PinType::Exec, other) | (other, PinType::Exec) => *other == PinType::Exec,
+            (PinType::Any, _) | (_, PinType::Any) => true,
+            (PinType::Num | PinType::Int, PinType::Num | PinType::Int) => true,
+            (PinType::List(to), PinType::List(from)) => to.accepts(from),
+            (PinType::Map(to), PinType::Map(from)) => to.accepts(from),
+            (to, from) => to == from,
+        }
+    }
+
+    /// Whether a value can be held by a pin of this type
+    pub fn matches(&self, var: &Var) -> bool {
+        match (self, var) {
+            (PinType::Any, _) => true,
+            (PinType::Exec, Var::Execution(_)) => true,
+            (PinType::Num, Var::Num(_) | Var::Int(_)) => true,
+            (PinType::Int, Var::Int(_)) => true,
+            (PinType::Bool, Var::Bool(_)) => true,
+            (PinType::String, Var::String(_)) => true,
+            (PinType::List(item), Var::List(list)) => list.iter().all(|v| item.matches(v)),
+            (PinType::Map(value), Var::Map(map)) => map.values().all(|v| value.matches(v)),
+            (PinType::Struct(name), Var::Struct(value)) => *name == value.type_name,
+            _ => false,
+        }
+    }
+
+    /// The value a pin of this type holds when nothing has been assigned to it
+    pub fn default_value(&self) -> Var {
+        match self {
+            PinType::Any => Var::Null,
+            PinType::Exec => Var::Execution(false),
+            PinType::Num => Var::Num(0.0),
+            PinType::Int => Var::Int(0),
+            PinType::Bool => Var::Bool(false),
+            PinType::String => Var::String(String::new()),
+            PinType::List(_) => Var::List(Vec::new()),
+            PinType::Map(_) => Var::Map(BTreeMap::new()),
+            PinType::Struct(name) => Var::Struct(Box::new(StructVar {
+                type_name: name.clone(),
+                fields: BTreeMap::new(),
+            })),
+        }
+    }
+}
+
+impl std::fmt::Display for PinType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PinType::List(item) => write!(f, "List<{item}>"),
+            PinType::Map(value) => write!(f, "Map<{value}>"),
+            PinType::Struct(name) => write!(f, "Struct<{name}>"),
+            other => write!(f, "{other:?}"),
+        }
+    }
+}
+
+impl Var {
+    pub fn is_execution(&self) -> bool {
+        matches!(self, Var::Execution(_))
+    }
+
+    /// Reads any numeric variant as a float. Integers beyond 2^53 lose precision
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Var::Num(value) => Some(*value),
+            Var::Int(value) => Some(*value as f64),
+            _ => None,
+        }
+    }
+
+    /// Reads any numeric variant as an integer, as long as no precision would be lost
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Var::Int(value) => Some(*value),
+            Var::Num(value)
+                if value.fract() == 0.0
+                    && *value >= i64::MIN as f64
+                    && *value < i64::MAX as f64 =>
+            {
+                Some(*value as i64)
+            }
+            _ => None,
+        }
+    }
+}
+
 impl<T: NamespacedType + Clone> std::default::Default for TypeRegistry<T> {
     fn default() -> Self {
         Self {
             types: Default::default(),
+            shadowed: Default::default(),
+            policy: Default::default(),
+            logger: None,
         }
     }
 }
 
 impl<T: NamespacedType + Clone> TypeRegistry<T> {
+    pub fn with_policy(policy: RegistryPolicy) -> Self {
+        Self {
+            policy,
+            ..Default::default()
+        }
+    }
+
+    pub fn policy(&self) -> RegistryPolicy {
+        self.policy
+    }
+
+    pub fn set_policy(&mut self, policy: RegistryPolicy) {
+        self.policy = policy;
+    }
+
+    pub fn set_logger(&mut self, logger: Logger) {
+        self.logger = Some(logger);
+    }
+
+    /// Registers a type, panicking if the registry's policy refuses it. Prefer [TypeRegistry::try_register] for types which are not built in
     pub fn register(&mut self, value: T) {
-        let GlobalName(n, t) = value.get_name();
-        if let Some(namespace_reg) = self.types.get_mut(&n) {
-            if namespace_reg.contains_key(&t) {
-                panic!("Cannot create duplicate type of {:?}", GlobalName(n, t));
+        if let Err(err) = self.try_register(value) {
+            panic!("Cannot register type: {}", err);
+        }
+    }
+
+    /// Registers a type, resolving name collisions according to the registry's [RegistryPolicy]
+    pub fn try_register(&mut self, value: T) -> Result<(), RegistryError> {
+        let name = value.get_name();
+        let value = Arc::new(value);
+        let Some(existing) = self.get(&name) else {
+            self.insert(name, value);
+            return Ok(());
+        };
+        match self.policy {
+            RegistryPolicy::Reject => return Err(RegistryError::Duplicate(name)),
+            RegistryPolicy::Override => (),
+            RegistryPolicy::ShadowWithWarning => {
+                if let Some(logger) = &self.logger {
+                    logger.warn(format!(
+                        "Type {:?} shadows an existing type of the same name",
+                        name
+                    ));
+                }
+                self.shadowed
+                    .entry(name.clone())
+                    .or_default()
+                    .push(existing);
             }
-            namespace_reg.insert(t, Arc::new(value));
-        } else {
-            let mut map = HashMap::new();
-            map.insert(t, Arc::new(value));
-            self.types.insert(n, map);
         }
+        self.insert(name, value);
+        Ok(())
+    }
+
+    /// Replaces a type regardless of policy, returning the previous entry if there was one
+    pub fn replace(&mut self, value: T) -> Option<Arc<T>> {
+        let name = value.get_name();
+        let previous = self.get(&name);
+        self.insert(name, Arc::new(value));
+        previous
+    }
+
+    /// Removes a type. If it was shadowing another entry, that entry becomes visible again
+    pub fn unregister(&mut self, name: &GlobalName) -> Result<Arc<T>, RegistryError> {
+        let removed = self
+            .types
+            .get_mut(&name.0)
+            .and_then(|map| map.remove(&name.1))
+            .ok_or_else(|| RegistryError::NotFound(name.clone()))?;
+        let restored = self.shadowed.get_mut(name).and_then(|stack| stack.pop());
+        if self
+            .shadowed
+            .get(name)
+            .is_some_and(|stack| stack.is_empty())
+        {
+            self.shadowed.remove(name);
+        }
+        if let Some(restored) = restored {
+            self.insert(name.clone(), restored);
+        }
+        Ok(removed)
+    }
+
+    /// Iterates every registered type in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (GlobalName, Arc<T>)> + '_ {
+        self.types.iter().flat_map(|(n, map)| {
+            map.iter()
+                .map(|(t, value)| (GlobalName(n.clone(), t.clone()), value.clone()))
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.types.values().map(HashMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Every namespace with at least one type, sorted by name
+    pub fn namespaces(&self) -> Vec<Namespace> {
+        let mut namespaces = self
+            .types
+            .iter()
+            .filter(|(_, map)| !map.is_empty())
+            .map(|(n, _)| n.clone())
+            .collect::<Vec<_>>();
+        namespaces.sort_by_key(|n| n.0.to_string());
+        namespaces
+    }
+
+    /// Names of every registered type, sorted by path
+    pub fn names(&self) -> Vec<GlobalName> {
+        let mut names = self.iter().map(|(name, _)| name).collect::<Vec<_>>();
+        names.sort_by_key(GlobalName::to_path);
+        names
+    }
+
+    /// Types directly inside a namespace, sorted by path
+    pub fn list_namespace(&self, namespace: &str) -> Vec<(GlobalName, Arc<T>)> {
+        let Some(namespace) = StringName::lookup(namespace).map(Namespace) else {
+            return Vec::new();
+        };
+        let Some(map) = self.types.get(&namespace) else {
+            return Vec::new();
+        };
+        let mut types = map
+            .iter()
+            .map(|(t, value)| (GlobalName(namespace.clone(), t.clone()), value.clone()))
+            .collect::<Vec<_>>();
+        types.sort_by_key(|(name, _)| name.to_path());
+        types
+    }
+
+    /// Types whose path matches a pattern, sorted by path.
+    ///
+    /// A trailing `*` matches any suffix, so `std.*` lists the entire standard library including nested namespaces. Without it the path must match exactly
+    pub fn list(&self, pattern: &str) -> Vec<(GlobalName, Arc<T>)> {
+        let mut types = self
+            .iter()
+            .filter(|(name, _)| match pattern.strip_suffix('*') {
+                Some(prefix) => name.to_path().starts_with(prefix),
+                None => name.to_path() == pattern,
+            })
+            .collect::<Vec<_>>();
+        types.sort_by_key(|(name, _)| name.to_path());
+        types
+    }
+
+    /// Types whose path fuzzily matches a query, best matches first.
+    ///
+    /// Every character of the query must appear in the path in order, ignoring case. Consecutive characters and characters at the start or end of a path segment rank higher
+    pub fn search(&self, query: &str) -> Vec<(GlobalName, Arc<T>)> {
+        let mut scored = self
+            .iter()
+            .filter_map(|(name, value)| {
+                let path = name.to_path();
+                fuzzy_score(query, &path).map(|score| (score, path, name, value))
+            })
+            .collect::<Vec<_>>();
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        scored
+            .into_iter()
+            .map(|(_, _, name, value)| (name, value))
+            .collect()
+    }
+
+    fn insert(&mut self, name: GlobalName, value: Arc<T>) {
+        let GlobalName(n, t) = name;
+        self.types.entry(n).or_default().insert(t, value);
     }
 
     pub fn get(&self, name: &GlobalName) -> Option<Arc<T>> {
@@ -81,15 +578,126 @@ impl<T: NamespacedType + Clone> TypeRegistry<T> {
     }
 }
 
+/// Scores how well `query` matches `text` as a subsequence, or `None` if it does not match at all
+fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text = text.to_lowercase().chars().collect::<Vec<_>>();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous = None;
+    for c in query.to_lowercase().chars() {
+        let found = position + text[position..].iter().position(|t| *t == c)?;
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || matches!(text[found - 1], '.' | '_') {
+            score += 3;
+        }
+        previous = Some(found);
+        position = found + 1;
+    }
+    // prefer queries which complete a segment, e.g. `add` over `addon`
+    if position == text.len() || text[position] == '.' {
+        score += 3;
+    }
+    // shorter paths are closer matches
+    Some(score * 100 - text.len() as i64)
+}
+
+impl std::fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RegistryError::Duplicate(name) => write!(f, "{:?} is already registered", name),
+            RegistryError::NotFound(name) => write!(f, "{:?} is not registered", name),
+        }
+    }
+}
+
+impl StringName {
+    fn names() -> &'static RwLock<HashSet<Interned>> {
+        static NAMES: OnceLock<RwLock<HashSet<Interned>>> = OnceLock::new();
+        NAMES.get_or_init(Default::default)
+    }
+
+    /// Finds the shared allocation of `name`, creating it the first time the name is seen
+    pub fn intern(name: &str) -> Self {
+        if let Some(interned) = Self::lookup(name) {
+            return interned;
+        }
+        let mut names = Self::names()
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        // another thread may have added it between the locks
+        if let Some(interned) = names.get(name) {
+            return Self(interned.0.clone());
+        }
+        let interned = Arc::new(name.to_string());
+        names.insert(Interned(interned.clone()));
+        Self(interned)
+    }
+
+    /// Finds `name` if it is already interned, without interning it. A name nothing was ever created with can not be
+    /// the key of any map, so queries with names from user data should use this over [StringName::intern]
+    pub fn lookup(name: &str) -> Option<Self> {
+        // a writer which panicked can at most have left the set without a new name, so poisoning is ignored
+        Self::names()
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(name)
+            .map(|interned| Self(interned.0.clone()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl std::borrow::Borrow<str> for Interned {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for StringName {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for StringName {}
+
+impl Hash for StringName {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.0).hash(state);
+    }
+}
+
+// ordered by content rather than pointer, so sorting names gives the same order every run
+impl PartialOrd for StringName {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for StringName {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
 impl From<&'static str> for StringName {
     fn from(value: &'static str) -> Self {
-        StringName(Arc::new(value.as_bytes().into()))
+        Self::intern(value)
     }
 }
 
 impl From<String> for StringName {
     fn from(value: String) -> Self {
-        StringName(Arc::new(value.into_bytes()))
+        Self::intern(&value)
     }
 }
 
@@ -104,9 +712,9 @@ impl<T: Into<StringName>> From<T> for TypeName {
     }
 }
 
-impl ToString for StringName {
-    fn to_string(&self) -> String {
-        String::from_utf8(self.0.iter().cloned().collect()).unwrap_or("".into())
+impl std::fmt::Display for StringName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
     }
 }
 
@@ -124,34 +732,233 @@ impl GlobalName {
         GlobalName(namespace.into(), type_name.into())
     }
 
+    /// Parses a path like [GlobalName::from_path], but only if both of its parts are already interned
+    pub fn lookup_path(path: &str) -> Option<Self> {
+        let (namespace, type_name) = path.rsplit_once(Self::PATH_DELIM).unwrap_or(("", path));
+        Some(GlobalName(
+            Namespace(StringName::lookup(namespace)?),
+            TypeName(StringName::lookup(type_name)?),
+        ))
+    }
+
+    /// The path this name is parsed from by [GlobalName::from_path]. Names without a namespace are just their type name
     pub fn to_path(&self) -> String {
-        let ns = self.0 .0.to_string();
-        let tn = self.1 .0.to_string();
-        format!("{ns}.{tn}")
+        self.to_string()
     }
 
     pub fn is_empty(&self) -> bool {
-        self.0 .0 .0.is_empty() && self.1 .0 .0.is_empty()
+        self.0 .0.is_empty() && self.1 .0.is_empty()
     }
 }
-impl std::fmt::Debug for GlobalName {
+
+impl std::fmt::Display for GlobalName {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if self.0 .0 .0.is_empty() {
-            f.write_fmt(format_args!("@{:}", self.1 .0.to_string()))
+        if self.0 .0.is_empty() {
+            f.write_str(self.1 .0.as_str())
         } else {
             f.write_fmt(format_args!(
-                "@{:}.{:}",
-                self.0 .0.to_string(),
-                self.1 .0.to_string()
+                "{}{}{}",
+                self.0 .0,
+                Self::PATH_DELIM,
+                self.1 .0
             ))
         }
     }
 }
 
+impl std::fmt::Debug for GlobalName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("@{self}"))
+    }
+}
+
 impl std::fmt::Debug for StringName {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let human_readable =
-            String::from_utf8(self.0.iter().cloned().collect::<Vec<u8>>()).unwrap_or("".into());
-        f.write_fmt(format_args!("StringName(\"{}\")", human_readable))
+        f.write_fmt(format_args!("StringName({:?})", self.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::{BTreeMap, HashMap},
+        sync::Arc,
+    };
+
+    use super::{
+        GlobalName, NamespacedType, PinType, RegisterLayout, RegistryError, RegistryPolicy,
+        SlotRegisters, StringName, TypeRegistry, Var, VarRegisters,
+    };
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Named(&'static str, i32);
+
+    impl NamespacedType for Named {
+        fn get_name(&self) -> GlobalName {
+            GlobalName::from_path(self.0)
+        }
+    }
+
+    #[test]
+    fn test_registry_listing() {
+        let mut reg = TypeRegistry::default();
+        for name in [
+            "std.math.add",
+            "std.math.subtract",
+            "std.print",
+            "game.addon",
+        ] {
+            reg.register(Named(name, 0));
+        }
+        assert_eq!(reg.len(), 4);
+        assert_eq!(reg.namespaces().len(), 3);
+
+        let paths = |types: Vec<(GlobalName, _)>| {
+            types
+                .into_iter()
+                .map(|(name, _)| name.to_path())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            paths(reg.list("std.math.*")),
+            vec!["std.math.add", "std.math.subtract"]
+        );
+        assert_eq!(paths(reg.list("std.*")).len(), 3);
+        assert_eq!(paths(reg.list("std.print")), vec!["std.print"]);
+        assert_eq!(paths(reg.list_namespace("std")), vec!["std.print"]);
+
+        let found = paths(reg.search("msub"));
+        assert_eq!(found, vec!["std.math.subtract"]);
+        let found = paths(reg.search("add"));
+        assert_eq!(found[0], "std.math.add");
+        assert!(found.contains(&"game.addon".to_string()));
+        assert!(reg.search("xyz").is_empty());
+    }
+
+    #[test]
+    fn test_registry_policy() {
+        let name = GlobalName::from_path("std.a");
+        let mut reg = TypeRegistry::default();
+        reg.register(Named("std.a", 1));
+        assert_eq!(
+            reg.try_register(Named("std.a", 2)),
+            Err(RegistryError::Duplicate(name.clone()))
+        );
+        assert_eq!(reg.get(&name).unwrap().1, 1);
+
+        reg.set_policy(RegistryPolicy::Override);
+        reg.try_register(Named("std.a", 2)).unwrap();
+        assert_eq!(reg.get(&name).unwrap().1, 2);
+        // overridden entries are gone for good
+        reg.unregister(&name).unwrap();
+        assert!(!reg.contains(&name));
+
+        let mut reg = TypeRegistry::with_policy(RegistryPolicy::ShadowWithWarning);
+        reg.register(Named("std.a", 1));
+        reg.register(Named("std.a", 2));
+        assert_eq!(reg.get(&name).unwrap().1, 2);
+        // shadowed entries come back once the shadowing one is removed
+        assert_eq!(reg.unregister(&name).unwrap().1, 2);
+        assert_eq!(reg.get(&name).unwrap().1, 1);
+        reg.unregister(&name).unwrap();
+        assert_eq!(
+            reg.unregister(&name).err(),
+            Some(RegistryError::NotFound(name.clone()))
+        );
+
+        assert_eq!(reg.replace(Named("std.a", 3)), None);
+        assert_eq!(reg.replace(Named("std.a", 4)).unwrap().1, 3);
+    }
+
+    #[test]
+    fn test_var_collections_ron() {
+        let text = r#"Map({"names": List([String("a"), String("b")]), "count": Num(2.0)})"#;
+        let var = ron::from_str::<Var>(text).unwrap();
+        let expected = Var::Map(BTreeMap::from([
+            (
+                "names".into(),
+                Var::List(vec![Var::String("a".into()), Var::String("b".into())]),
+            ),
+            ("count".into(), Var::Num(2.0)),
+        ]));
+        assert_eq!(var, expected);
+
+        let round_trip = ron::from_str::<Var>(&ron::to_string(&var).unwrap()).unwrap();
+        assert_eq!(round_trip, expected);
+    }
+
+    #[test]
+    fn test_pin_type_accepts() {
+        assert!(PinType::Num.accepts(&PinType::Int));
+        assert!(PinType::Any.accepts(&PinType::list_of(PinType::Num)));
+        assert!(PinType::list_of(PinType::Any).accepts(&PinType::list_of(PinType::Bool)));
+        assert!(!PinType::list_of(PinType::Num).accepts(&PinType::list_of(PinType::Bool)));
+        assert!(!PinType::Bool.accepts(&PinType::String));
+        // execution flow never mixes with data, not even through Any
+        assert!(!PinType::Any.accepts(&PinType::Exec));
+        assert!(!PinType::Exec.accepts(&PinType::Any));
+        assert!(PinType::Exec.accepts(&PinType::Exec));
+        assert!(!PinType::Struct("a.B".into()).accepts(&PinType::Struct("a.C".into())));
+    }
+
+    #[test]
+    fn test_slot_registers() {
+        let mut layout = RegisterLayout::new(["b".into(), "a".into(), "b".into()]);
+        layout.extend(["extra".into(), "a".into()]);
+        assert_eq!(layout.slot(&"a".into()), Some(0));
+        assert_eq!(layout.slot(&"b".into()), Some(1));
+        assert_eq!(layout.slot(&"extra".into()), Some(2));
+        assert_eq!(layout.len(), 3);
+
+        let named = VarRegisters(HashMap::from([
+            ("b".into(), Var::Int(2)),
+            ("unknown".into(), Var::Int(3)),
+        ]));
+        let mut slots = SlotRegisters::from_registers(Arc::new(layout), named);
+        assert_eq!(slots.get(0), &Var::Null);
+        assert_eq!(slots.get(1), &Var::Int(2));
+        assert_eq!(slots.get(7), &Var::Null);
+        slots.set(0, Var::Int(1));
+        assert_eq!(slots.get_named(&"a".into()), &Var::Int(1));
+
+        // empty slots are left out, and names without a slot were dropped
+        assert_eq!(
+            slots.to_registers(),
+            VarRegisters(HashMap::from([
+                ("a".into(), Var::Int(1)),
+                ("b".into(), Var::Int(2)),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_string_name_interning() {
+        let a: StringName = "interned".into();
+        let b: StringName = (String::from("intern") + "ed").into();
+        assert_eq!(a, b.clone());
+        // both share the allocation made by whichever was interned first
+        assert!(std::ptr::eq(a.as_str(), b.as_str()));
+        assert_ne!(a, StringName::from("other"));
+        assert_eq!(a.to_string(), "interned");
+        assert_eq!(format!("{a:?}"), "StringName(\"interned\")");
+
+        // looking a name up never interns it
+        assert_eq!(StringName::lookup("interned"), Some(a));
+        assert_eq!(StringName::lookup("never.interned"), None);
+        assert_eq!(StringName::lookup("never.interned"), None);
+        assert_eq!(GlobalName::lookup_path("never.interned"), None);
+        let name = GlobalName::from_path("std.looked_up");
+        assert_eq!(GlobalName::lookup_path("std.looked_up"), Some(name));
+    }
+
+    #[test]
+    fn test_global_name_display() {
+        let name = GlobalName::from_path("std.math.add");
+        assert_eq!(name.to_string(), "std.math.add");
+        assert_eq!(format!("{name:?}"), "@std.math.add");
+        // names without a namespace are written without a leading delimiter, and still read back the same
+        let bare = GlobalName::from_path("add");
+        assert_eq!(bare.to_path(), "add");
+        assert_eq!(GlobalName::from_path(bare.to_path()), bare);
     }
 }
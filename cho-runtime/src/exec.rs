@@ -1,14 +1,13 @@
-use std::{collections::HashMap, fs::File, path::PathBuf, sync::Arc};
+use std::{collections::BTreeMap, sync::Arc};
 
 use cho_lib::{
-    filetype::ScriptProto,
     nodes::{Node, NodeData, NodeError, ScriptNode},
-    types::{GlobalName, Var, VarRegisters},
+    types::{GlobalName, Globals, Var, VarRegisters},
     Environment,
 };
 use serde::{Deserialize, Serialize};
 
-use crate::project::ProjectFile;
+use crate::{loader::ModuleLoader, project::ProjectFile};
 
 pub struct Execution {
     env: Environment,
@@ -20,11 +19,13 @@ pub struct Execution {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ExecutableConfig {
     entry: String,
-    start_frame_data: HashMap<String, Var>,
+    start_frame_data: BTreeMap<String, Var>,
 }
 
 impl Execution {
-    pub fn new(env: Environment, entry: GlobalName, config: &ProjectFile) -> Self {
+    /// Globals of the execution start out as the configured start frame data, separate from those of `env`
+    pub fn new(mut env: Environment, entry: GlobalName, config: &ProjectFile) -> Self {
+        env.globals = Globals::new(Self::start_frame_data(config));
         Self {
             env,
             entry: if entry.is_empty() {
@@ -37,6 +38,13 @@ impl Execution {
         }
     }
 
+    /// The environment after any scripts loaded by this execution
+    pub fn env(&self) -> &Environment {
+        &self.env
+    }
+
+    // the error path ends execution, so its size is irrelevant
+    #[allow(clippy::result_large_err)]
     pub fn run(&mut self) -> Result<VarRegisters, (NodeError, Vec<Arc<Node>>)> {
         if !self.env.nodes.contains(&self.entry) {
             let target = self.entry.clone();
@@ -57,12 +65,7 @@ impl Execution {
         self.call_stack.push(entry);
 
         let aenv = Arc::new(self.env.clone());
-        let mut frame_data = VarRegisters::new();
-        if let Some(exec_data) = &self.config.executable {
-            for (k, v) in &exec_data.start_frame_data {
-                frame_data.0.insert(k.clone().into(), v.clone());
-            }
-        }
+        let mut frame_data = Self::start_frame_data(&self.config);
 
         while !self.call_stack.is_empty() {
             let Some(top) = self.call_stack.pop() else {
@@ -80,41 +83,39 @@ impl Execution {
         Ok(frame_data)
     }
 
-    fn try_get_script_for(&mut self, name: &GlobalName) -> Option<ScriptNode> {
-        let mut fpath = PathBuf::new();
-        for element in name.to_path().split('.') {
-            fpath.push(element);
+    fn start_frame_data(config: &ProjectFile) -> VarRegisters {
+        let mut frame_data = VarRegisters::new();
+        if let Some(exec_data) = &config.executable {
+            for (k, v) in &exec_data.start_frame_data {
+                frame_data.0.insert(k.clone().into(), v.clone());
+            }
         }
-        fpath.set_extension("cho");
-        if !fpath.exists() {
+        frame_data
+    }
+
+    fn try_get_script_for(&mut self, name: &GlobalName) -> Option<ScriptNode> {
+        let loader = ModuleLoader::new(self.config.get_source_roots());
+        if let Err(err) = loader.load(&mut self.env, name) {
+            self.env
+                .logger
+                .error(format!("Failed to load script {:?}: {}", name, err));
             return None;
         }
-        let Ok(file) = File::open(fpath) else {
-            return None;
-        };
-        let Ok(proto) = ron::de::from_reader::<_, ScriptProto>(file) else {
-            return None;
-        };
 
-        self.env.scripts.register(proto.to_script(&self.env.nodes));
-
-        match self.env.scripts.get(name) {
-            Some(script) => Some(ScriptNode {
-                name: name.clone(),
-                func: "main".into(),
-                script,
-            }),
-            None => None,
-        }
+        self.env.scripts.get(name).map(|script| ScriptNode {
+            name: name.clone(),
+            func: "main".into(),
+            script,
+        })
     }
 }
 
 impl Default for ExecutableConfig {
     fn default() -> Self {
-        let mut frame = HashMap::new();
+        let mut frame = BTreeMap::new();
         frame.insert("a".into(), Var::Bool(true));
         frame.insert("b".into(), Var::String("A string".into()));
-        frame.insert("c".into(), Var::Num(3.1415));
+        frame.insert("c".into(), Var::Num(std::f64::consts::PI));
 
         Self {
             entry: "main".into(),
@@ -0,0 +1,192 @@
+use std::path::{Path, PathBuf};
+
+use cho_lib::{
+    filetype::{Diagnostic, ScriptProto, Versioned},
+    types::{GlobalName, RegistryError},
+    Environment,
+};
+
+pub const SCRIPT_EXTENSION: &str = "cho";
+
+/// Finds scripts in a list of source roots and loads them into an [Environment] along with everything they import
+pub struct ModuleLoader {
+    roots: Vec<PathBuf>,
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    /// No source root has a file for the script
+    NotFound {
+        name: GlobalName,
+        searched: Vec<PathBuf>,
+    },
+    Io {
+        path: PathBuf,
+        msg: String,
+    },
+    Parse {
+        path: PathBuf,
+        msg: String,
+    },
+    /// Scripts which import each other, starting and ending with the same script
+    Cycle {
+        chain: Vec<GlobalName>,
+    },
+    Registry(RegistryError),
+    Invalid {
+        name: GlobalName,
+        diagnostics: Vec<Diagnostic>,
+    },
+}
+
+impl ModuleLoader {
+    pub fn new(roots: impl IntoIterator<Item = impl Into<PathBuf>>) -> Self {
+        Self {
+            roots: roots.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Path of the script in the first source root which has it
+    pub fn find(&self, name: &GlobalName) -> Result<PathBuf, LoadError> {
+        let searched = self
+            .roots
+            .iter()
+            .map(|root| {
+                let mut path = root.clone();
+                for element in name.to_path().split(GlobalName::PATH_DELIM) {
+                    if !element.is_empty() {
+                        path.push(element);
+                    }
+                }
+                path.set_extension(SCRIPT_EXTENSION);
+                path
+            })
+            .collect::<Vec<_>>();
+        match searched.iter().find(|path| path.exists()) {
+            Some(path) => Ok(path.clone()),
+            None => Err(LoadError::NotFound {
+                name: name.clone(),
+                searched,
+            }),
+        }
+    }
+
+    /// Every script file under the source roots, sorted within each root. Hidden directories are skipped
+    pub fn scripts(&self) -> Result<Vec<PathBuf>, LoadError> {
+        let mut found = Vec::new();
+        for root in self.roots.iter() {
+            let start = found.len();
+            collect_scripts(root, &mut found)?;
+            found[start..].sort();
+        }
+        Ok(found)
+    }
+
+    /// Loads a script and, before it, everything it imports. Scripts which are already registered are skipped
+    pub fn load(&self, env: &mut Environment, name: &GlobalName) -> Result<(), LoadError> {
+        self.load_with_chain(env, name, &mut Vec::new())
+    }
+
+    fn load_with_chain(
+        &self,
+        env: &mut Environment,
+        name: &GlobalName,
+        chain: &mut Vec<GlobalName>,
+    ) -> Result<(), LoadError> {
+        if let Some(start) = chain.iter().position(|loading| loading == name) {
+            let mut cycle = chain[start..].to_vec();
+            cycle.push(name.clone());
+            return Err(LoadError::Cycle { chain: cycle });
+        }
+        if env.scripts.contains(name) {
+            return Ok(());
+        }
+
+        let path = self.find(name)?;
+        let text = std::fs::read_to_string(&path).map_err(|err| LoadError::Io {
+            path: path.clone(),
+            msg: err.to_string(),
+        })?;
+        let proto = ScriptProto::from_ron(&text).map_err(|err| LoadError::Parse {
+            path: path.clone(),
+            msg: err.to_string(),
+        })?;
+        env.logger
+            .debug(format!("Loading script {:?} from {}", name, path.display()));
+
+        chain.push(name.clone());
+        for import in proto.imports.iter() {
+            self.load_with_chain(env, &GlobalName::from_path(import.clone()), chain)?;
+        }
+        chain.pop();
+
+        proto.register_structs(env).map_err(LoadError::Registry)?;
+        let script = proto
+            .to_script(env)
+            .map_err(|diagnostics| LoadError::Invalid {
+                name: name.clone(),
+                diagnostics,
+            })?;
+        env.scripts
+            .try_register(script)
+            .map_err(LoadError::Registry)
+    }
+}
+
+fn collect_scripts(dir: &Path, found: &mut Vec<PathBuf>) -> Result<(), LoadError> {
+    let entries = std::fs::read_dir(dir).map_err(|err| LoadError::Io {
+        path: dir.to_path_buf(),
+        msg: err.to_string(),
+    })?;
+    for entry in entries {
+        let path = entry
+            .map_err(|err| LoadError::Io {
+                path: dir.to_path_buf(),
+                msg: err.to_string(),
+            })?
+            .path();
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if path.is_dir() && !hidden {
+            collect_scripts(&path, found)?;
+        } else if path.extension().is_some_and(|ext| ext == SCRIPT_EXTENSION) {
+            found.push(path);
+        }
+    }
+    Ok(())
+}
+
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::NotFound { name, searched } => {
+                write!(f, "Could not find script {:?}, searched:", name)?;
+                for path in searched {
+                    write!(f, "\n  {}", path.display())?;
+                }
+                Ok(())
+            }
+            LoadError::Io { path, msg } => write!(f, "Failed to read {}: {}", path.display(), msg),
+            LoadError::Parse { path, msg } => {
+                write!(f, "Failed to parse {}: {}", path.display(), msg)
+            }
+            LoadError::Cycle { chain } => {
+                let chain = chain
+                    .iter()
+                    .map(|name| format!("{:?}", name))
+                    .collect::<Vec<_>>()
+                    .join(" -> ");
+                write!(f, "Scripts import each other: {}", chain)
+            }
+            LoadError::Registry(err) => write!(f, "{}", err),
+            LoadError::Invalid { name, diagnostics } => {
+                write!(f, "Script {:?} failed validation:", name)?;
+                for diagnostic in diagnostics {
+                    write!(f, "\n  {}", diagnostic)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
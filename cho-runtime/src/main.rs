@@ -1,12 +1,17 @@
+use std::{fs, path::Path};
+
 use cho_lib::{
+    filetype::{ScriptProto, Severity, Versioned},
     types::{GlobalName, NamespacedType},
     Environment,
 };
 use clap::{Parser, Subcommand};
 use exec::{ExecutableConfig, Execution};
+use loader::ModuleLoader;
 use project::{ProjectFile, PROJECT_CONFIG_FILE};
 
 mod exec;
+mod loader;
 mod project;
 
 #[derive(Parser)]
@@ -33,13 +38,23 @@ enum Commands {
         #[arg(short, long)]
         lib: bool,
     },
+    /// Rewrites the project file and every script under the source roots in canonical form
+    Fmt {
+        /// Only report unformatted files, exiting with an error if there are any
+        #[arg(long)]
+        check: bool,
+    },
+    /// Validates every script under the source roots without running anything
+    Check,
+    /// Rewrites the project file and every script written by an older format version in the latest version
+    Migrate,
 }
 
-const FLAG_DUMP_ENV: &'static str = "CHO_DUMP_ENV";
+const FLAG_DUMP_ENV: &str = "CHO_DUMP_ENV";
 
 fn main() {
     let cli = CliData::parse();
-    let env = Environment::new();
+    let mut env = Environment::new();
     let Some(cmd) = cli.command else {
         env.logger
             .warn("No commands provided. Refer to the help page for available commands");
@@ -54,11 +69,26 @@ fn main() {
             verbose,
         } => {
             if verbose {
-                log::set_max_level(log::LevelFilter::Debug);
+                env.set_log_level(log::LevelFilter::Debug);
             }
             cmd_run(env, entry, dump_env)
         }
         Commands::New { path, lib } => cmd_new(env, path, lib),
+        Commands::Fmt { check } => {
+            if !cmd_fmt(env, check) {
+                std::process::exit(1);
+            }
+        }
+        Commands::Check => {
+            if !cmd_check(env) {
+                std::process::exit(1);
+            }
+        }
+        Commands::Migrate => {
+            if !cmd_migrate(env) {
+                std::process::exit(1);
+            }
+        }
     }
 }
 
@@ -75,6 +105,241 @@ fn cmd_new(env: Environment, in_path: Option<String>, is_lib: bool) {
     ProjectFile::write_default_to(spath, &env.logger, config);
 }
 
+/// Returns whether every file was already formatted or was rewritten successfully
+fn cmd_fmt(env: Environment, check: bool) -> bool {
+    let Some(config) = ProjectFile::get_from_cwd(&env.logger) else {
+        env.logger.error(format!("Failed to find configuration file at this directory. Make sure you have a {} file in this directory", PROJECT_CONFIG_FILE));
+        return false;
+    };
+    let scripts = match ModuleLoader::new(config.get_source_roots()).scripts() {
+        Ok(scripts) => scripts,
+        Err(err) => {
+            env.logger.error(format!("{}", err));
+            return false;
+        }
+    };
+
+    let mut ok = format_file(&env, Path::new(PROJECT_CONFIG_FILE), check, |text| {
+        let config = ProjectFile::from_ron(text).map_err(|err| err.to_string())?;
+        config.to_canonical_string().map_err(|err| err.to_string())
+    });
+    for path in scripts {
+        ok &= format_file(&env, &path, check, |text| {
+            let proto = ScriptProto::from_ron(text).map_err(|err| err.to_string())?;
+            proto.to_canonical_string().map_err(|err| err.to_string())
+        });
+    }
+    ok
+}
+
+fn format_file(
+    env: &Environment,
+    path: &Path,
+    check: bool,
+    format: impl FnOnce(&str) -> Result<String, String>,
+) -> bool {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) => {
+            env.logger
+                .error(format!("Failed to read {}: {}", path.display(), err));
+            return false;
+        }
+    };
+    let formatted = match format(&text) {
+        Ok(formatted) => formatted,
+        Err(msg) => {
+            env.logger
+                .error(format!("Failed to parse {}: {}", path.display(), msg));
+            return false;
+        }
+    };
+    if formatted == text {
+        return true;
+    }
+    if check {
+        env.logger
+            .error(format!("{} is not formatted", path.display()));
+        return false;
+    }
+    if let Err(err) = fs::write(path, formatted) {
+        env.logger
+            .error(format!("Failed to write {}: {}", path.display(), err));
+        return false;
+    }
+    env.logger.info(format!("Formatted {}", path.display()));
+    true
+}
+
+/// Returns whether every script is free of errors. Warnings are reported but do not fail the check
+fn cmd_check(env: Environment) -> bool {
+    let Some(config) = ProjectFile::get_from_cwd(&env.logger) else {
+        env.logger.error(format!("Failed to find configuration file at this directory. Make sure you have a {} file in this directory", PROJECT_CONFIG_FILE));
+        return false;
+    };
+    let loader = ModuleLoader::new(config.get_source_roots());
+    let scripts = match loader.scripts() {
+        Ok(scripts) => scripts,
+        Err(err) => {
+            env.logger.error(format!("{}", err));
+            return false;
+        }
+    };
+
+    let (mut errors, mut warnings) = (0, 0);
+    for path in scripts.iter() {
+        let proto = match fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| ScriptProto::from_ron(&text).map_err(|err| err.to_string()))
+        {
+            Ok(proto) => proto,
+            Err(msg) => {
+                env.logger.error(format!("{}: {}", path.display(), msg));
+                errors += 1;
+                continue;
+            }
+        };
+
+        // each script is checked against only what it imports
+        let mut script_env = env.clone();
+        for import in proto.imports.iter() {
+            if let Err(err) = loader.load(&mut script_env, &GlobalName::from_path(import.clone())) {
+                env.logger.error(format!("{}: {}", path.display(), err));
+                errors += 1;
+            }
+        }
+        if let Err(err) = proto.register_structs(&mut script_env) {
+            env.logger.error(format!("{}: {}", path.display(), err));
+            errors += 1;
+        }
+        for diagnostic in proto.validate(&script_env) {
+            match diagnostic.severity {
+                Severity::Warning => {
+                    env.logger
+                        .warn(format!("{}: {}", path.display(), diagnostic));
+                    warnings += 1;
+                }
+                Severity::Error => {
+                    env.logger
+                        .error(format!("{}: {}", path.display(), diagnostic));
+                    errors += 1;
+                }
+            }
+        }
+    }
+
+    env.logger.info(format!(
+        "Checked {} scripts: {} errors, {} warnings",
+        scripts.len(),
+        errors,
+        warnings
+    ));
+    errors == 0
+}
+
+/// Returns whether every file is now at the latest format version
+fn cmd_migrate(env: Environment) -> bool {
+    // the project file may itself be too old to be read normally
+    let mut ok = migrate_file(
+        &env,
+        Path::new(PROJECT_CONFIG_FILE),
+        |config: ProjectFile| config.to_canonical_string(),
+    );
+    let Some(config) = ProjectFile::get_from_cwd(&env.logger) else {
+        env.logger.error(format!("Failed to find configuration file at this directory. Make sure you have a {} file in this directory", PROJECT_CONFIG_FILE));
+        return false;
+    };
+    let scripts = match ModuleLoader::new(config.get_source_roots()).scripts() {
+        Ok(scripts) => scripts,
+        Err(err) => {
+            env.logger.error(format!("{}", err));
+            return false;
+        }
+    };
+    for path in scripts {
+        ok &= migrate_file(&env, &path, |proto: ScriptProto| {
+            proto.to_canonical_string()
+        });
+    }
+    ok
+}
+
+fn migrate_file<T: Versioned>(
+    env: &Environment,
+    path: &Path,
+    write: impl FnOnce(T) -> Result<String, ron::Error>,
+) -> bool {
+    let mut doc = match fs::read_to_string(path)
+        .map_err(|err| err.to_string())
+        .and_then(|text| ron::de::from_str::<T>(&text).map_err(|err| err.to_string()))
+    {
+        Ok(doc) => doc,
+        Err(msg) => {
+            env.logger
+                .error(format!("Failed to read {}: {}", path.display(), msg));
+            return false;
+        }
+    };
+    let found = match doc.migrate() {
+        Ok(found) => found,
+        Err(err) => {
+            env.logger.error(format!("{}: {}", path.display(), err));
+            return false;
+        }
+    };
+    if found == T::FORMAT_VERSION {
+        return true;
+    }
+    match write(doc)
+        .map_err(|err| err.to_string())
+        .and_then(|text| fs::write(path, text).map_err(|err| err.to_string()))
+    {
+        Ok(()) => {
+            env.logger.info(format!(
+                "Migrated {} from version {} to {}",
+                path.display(),
+                found,
+                T::FORMAT_VERSION
+            ));
+            true
+        }
+        Err(msg) => {
+            env.logger
+                .error(format!("Failed to write {}: {}", path.display(), msg));
+            false
+        }
+    }
+}
+
+fn print_env(env: &Environment) {
+    env.logger.info("=== DUMPING ENVIRONMENT ===");
+    env.logger.info(format!("Flags: {:?}", env.flags));
+    env.logger.info(format!("Nodes ({}):", env.nodes.len()));
+    for namespace in env.nodes.namespaces() {
+        let namespace = namespace.0.to_string();
+        env.logger.info(format!("  {namespace}:"));
+        for (name, _) in env.nodes.list_namespace(&namespace) {
+            env.logger.info(format!("    {}", name.1 .0));
+        }
+    }
+    env.logger.info(format!("Scripts ({}):", env.scripts.len()));
+    for name in env.scripts.names() {
+        env.logger.info(format!("  {}", name.to_path()));
+    }
+    env.logger.info(format!("Structs ({}):", env.structs.len()));
+    for name in env.structs.names() {
+        env.logger.info(format!("  {}", name.to_path()));
+    }
+    let globals = env.globals.snapshot();
+    env.logger.info(format!("Globals ({}):", globals.0.len()));
+    let mut globals = globals.0.into_iter().collect::<Vec<_>>();
+    globals.sort_by_key(|(name, _)| name.to_string());
+    for (name, value) in globals {
+        env.logger.info(format!("  {} = {:?}", name, value));
+    }
+    env.logger.info("=== END DUMP ===");
+}
+
 fn cmd_run(mut env: Environment, entry: Option<String>, dump_env: bool) {
     let Some(config) = ProjectFile::get_from_cwd(&env.logger) else {
         env.logger.error(format!("Failed to find configuration file at this directory. Make sure you have a {} file in this directory", PROJECT_CONFIG_FILE));
@@ -87,9 +352,7 @@ fn cmd_run(mut env: Environment, entry: Option<String>, dump_env: bool) {
     let mut exe = Execution::new(env.clone(), entry, &config);
     let output = exe.run();
     if env.flags.contains(&FLAG_DUMP_ENV.into()) {
-        env.logger.info("=== DUMPING ENVIRONMENT ===");
-        env.logger.info(format!("{:?}", env));
-        env.logger.info("=== END DUMP ===");
+        print_env(exe.env());
     }
     match output {
         Ok(last_frame) => {
@@ -1,17 +1,26 @@
 use std::{fs::File, path::Path};
 
-use cho_lib::logger::Logger;
+use cho_lib::{
+    filetype::{FormatError, ScriptProto, Versioned},
+    logger::Logger,
+};
 use ron::ser::PrettyConfig;
 use serde::{Deserialize, Serialize};
 
 use crate::exec::ExecutableConfig;
 
-pub const PROJECT_CONFIG_FILE: &'static str = "choreo.ron";
+pub const PROJECT_CONFIG_FILE: &str = "choreo.ron";
 
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct ProjectFile {
+    /// Files written before versions were recorded read as version 0
+    #[serde(default)]
+    pub format_version: u32,
     pub meta: Metadata,
     pub executable: Option<ExecutableConfig>,
+    /// Directories scripts are searched in, in order. Defaults to the project directory
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub source_roots: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -24,6 +33,38 @@ pub struct Metadata {
     license: String,
 }
 
+impl Default for ProjectFile {
+    fn default() -> Self {
+        Self {
+            format_version: Self::FORMAT_VERSION,
+            meta: Metadata::default(),
+            executable: None,
+            source_roots: Vec::new(),
+        }
+    }
+}
+
+impl Versioned for ProjectFile {
+    const FORMAT_VERSION: u32 = 1;
+
+    fn format_version(&self) -> u32 {
+        self.format_version
+    }
+
+    fn set_format_version(&mut self, version: u32) {
+        self.format_version = version;
+    }
+
+    /// Version 0 is read-compatible with the latest, so there is nothing to rewrite
+    fn upgrade_from(&mut self, version: u32) -> Result<(), FormatError> {
+        match version {
+            // version 1 only started recording the version
+            0 => Ok(()),
+            from => Err(FormatError::NoUpgrade { from }),
+        }
+    }
+}
+
 impl Default for Metadata {
     fn default() -> Self {
         Self {
@@ -63,14 +104,31 @@ impl ProjectFile {
         }
     }
 
+    pub fn get_source_roots(&self) -> Vec<String> {
+        if self.source_roots.is_empty() {
+            return vec![".".into()];
+        }
+        self.source_roots.clone()
+    }
+
+    /// Writes this project file with the same formatting as scripts
+    pub fn to_canonical_string(&self) -> Result<String, ron::Error> {
+        let mut text = ron::ser::to_string_pretty(self, ScriptProto::canonical_config())?;
+        text.push('\n');
+        Ok(text)
+    }
+
     pub fn get_from_cwd(log: &Logger) -> Option<Self> {
-        let Ok(file) = File::open(PROJECT_CONFIG_FILE) else {
+        let Ok(text) = std::fs::read_to_string(PROJECT_CONFIG_FILE) else {
             log.error("Failed to open project file");
             return None;
         };
-        match ron::de::from_reader::<_, Self>(file) {
-            Ok(val) => Some(val),
-            Err(_) => None,
+        match Self::from_ron(&text) {
+            Ok(config) => Some(config),
+            Err(err) => {
+                log.error(format!("Failed to parse project file: {}", err));
+                None
+            }
         }
     }
 }